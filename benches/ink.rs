@@ -1,8 +1,14 @@
+use contract_build::{BuildMode, OptimizationPasses};
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use ink::env::DefaultEnvironment;
 use subxt_signer::sr25519::dev;
 
-use schlau::{drink::runtime::MinimalRuntime, drink_api::CallArgs, ink::InkDrink};
+use schlau::{
+    contracts::{chain_extension::register_chain_extension, ContractsRuntime},
+    drink::runtime::MinimalRuntime,
+    drink_api::CallArgs,
+    ink::{InkBuildOptions, InkDrink, InstantiateOptions},
+};
 
 macro_rules! ink_contract_bench {
     ( $name:ident, $contract:ident, $contract_ref:ident, $message:ident, $args:tt) => {
@@ -56,5 +62,172 @@ ink_contract_bench!(
 
 ink_contract_bench!(nop, Nop, NopRef, baseline, [0]);
 
-criterion_group!(benches, baseline, sha3, odd_product, triangle_number);
+fn mapping_reads(c: &mut Criterion) {
+    use preload::preload::{Preload, PreloadRef};
+
+    let mut group = c.benchmark_group("mapping_reads");
+    group.sample_size(30);
+
+    for entries in [100, 1_000] {
+        let mut ink_drink = InkDrink::<DefaultEnvironment, MinimalRuntime>::new();
+        let contract = ink_drink
+            .build_and_instantiate_with::<_, Preload, _, _>(
+                "contracts/ink/preload/Cargo.toml",
+                &mut PreloadRef::with_entries(entries),
+                InstantiateOptions::new(),
+            )
+            .call_builder;
+
+        let message = contract.sum(1_000);
+        let call_args = CallArgs::from_call_builder(dev::alice(), &message).with_max_gas_limit();
+
+        let id = BenchmarkId::new(format!("ink({})", schlau::target_str()), entries);
+        group.bench_function(id, |b| {
+            b.iter(|| ink_drink.drink.call(call_args.clone()).unwrap())
+        });
+    }
+
+    group.finish()
+}
+
+/// Compare the `wasm-opt` optimization levels, which have no effect when targeting RISC-V.
+fn optimization_passes(c: &mut Criterion) {
+    use crypto::crypto::{Crypto, CryptoRef};
+
+    let mut group = c.benchmark_group("optimization_passes");
+    group.sample_size(30);
+
+    for passes in [
+        OptimizationPasses::Zero,
+        OptimizationPasses::Three,
+        OptimizationPasses::Z,
+    ] {
+        let options = InkBuildOptions::default().with_optimization_passes(passes);
+        let mut ink_drink =
+            InkDrink::<DefaultEnvironment, MinimalRuntime>::new().with_build_options(options);
+        let contract = ink_drink.build_and_instantiate::<_, Crypto, _, _>(
+            "contracts/ink/crypto/Cargo.toml",
+            &mut CryptoRef::new(),
+        );
+
+        let message = contract.sha3(2000);
+        let call_args = CallArgs::from_call_builder(dev::alice(), &message).with_max_gas_limit();
+
+        let id = BenchmarkId::new(format!("ink({})", schlau::target_str()), passes);
+        group.bench_function(id, |b| {
+            b.iter(|| ink_drink.drink.call(call_args.clone()).unwrap())
+        });
+    }
+
+    group.finish()
+}
+
+/// Measure the overhead of the debug buffer with a contract printing debug messages.
+fn debug_messages(c: &mut Criterion) {
+    use crypto::crypto::{Crypto, CryptoRef};
+
+    let mut group = c.benchmark_group("debug_messages");
+    group.sample_size(30);
+
+    for enabled in [false, true] {
+        // debug messages are only compiled into debug builds
+        let options = InkBuildOptions::default().with_build_mode(BuildMode::Debug);
+        let mut ink_drink =
+            InkDrink::<DefaultEnvironment, MinimalRuntime>::new().with_build_options(options);
+        let contract = ink_drink.build_and_instantiate::<_, Crypto, _, _>(
+            "contracts/ink/crypto/Cargo.toml",
+            &mut CryptoRef::new(),
+        );
+        ink_drink.drink.set_debug_messages(enabled);
+
+        let message = contract.sha3(2000);
+        let call_args = CallArgs::from_call_builder(dev::alice(), &message).with_max_gas_limit();
+
+        let parameter = if enabled { "enabled" } else { "disabled" };
+        let id = BenchmarkId::new(format!("ink({})", schlau::target_str()), parameter);
+        group.bench_function(id, |b| {
+            b.iter(|| ink_drink.drink.call(call_args.clone()).unwrap())
+        });
+    }
+
+    group.finish()
+}
+
+/// Benchmark a message of any ink! contract by its directory, encoding the call via the contract
+/// metadata instead of linking the contract crate.
+fn metadata_bench(c: &mut Criterion, contract: &str, message: &str, args: &[u32]) {
+    let mut group = c.benchmark_group(format!("{message}_from_metadata"));
+    group.sample_size(30);
+
+    for arg in args {
+        let mut ink_drink = InkDrink::<DefaultEnvironment, MinimalRuntime>::new();
+        let contract = ink_drink
+            .build_and_instantiate_from_metadata(
+                &format!("contracts/ink/{contract}/Cargo.toml"),
+                "new",
+                &[],
+                InstantiateOptions::new(),
+            )
+            .unwrap();
+        let call_args = contract
+            .call_args(dev::alice(), message, &[serde_json::json!(arg)])
+            .unwrap();
+
+        let id = BenchmarkId::new(format!("ink({})", schlau::target_str()), arg);
+        group.bench_function(id, |b| {
+            b.iter(|| ink_drink.drink.call(call_args.clone()).unwrap())
+        });
+    }
+
+    group.finish()
+}
+
+fn sha3_from_metadata(c: &mut Criterion) {
+    metadata_bench(c, "crypto", "sha3", &[2000, 4000, 8000])
+}
+
+fn chain_extension(c: &mut Criterion) {
+    use chain_ext::{
+        chain_ext::{ChainExt, ChainExtRef},
+        EchoEnvironment, ECHO_EXTENSION_ID, ECHO_FUNCTION_ID,
+    };
+
+    register_chain_extension(ECHO_EXTENSION_ID, ECHO_FUNCTION_ID, |input| {
+        Ok(input.to_vec())
+    });
+
+    let mut group = c.benchmark_group("chain_extension");
+    group.sample_size(30);
+
+    for args in [1_000, 2_000, 4_000] {
+        let mut ink_drink = InkDrink::<EchoEnvironment, ContractsRuntime>::new();
+        let contract = ink_drink.build_and_instantiate::<_, ChainExt, _, _>(
+            "contracts/ink/chain_ext/Cargo.toml",
+            &mut ChainExtRef::new(),
+        );
+
+        let message = contract.echo(args);
+        let call_args = CallArgs::from_call_builder(dev::alice(), &message).with_max_gas_limit();
+
+        let id = BenchmarkId::new(format!("ink({})", schlau::target_str()), args);
+        group.bench_function(id, |b| {
+            b.iter(|| ink_drink.drink.call(call_args.clone()).unwrap())
+        });
+    }
+
+    group.finish()
+}
+
+criterion_group!(
+    benches,
+    baseline,
+    sha3,
+    sha3_from_metadata,
+    odd_product,
+    triangle_number,
+    chain_extension,
+    debug_messages,
+    mapping_reads,
+    optimization_passes
+);
 criterion_main!(benches);
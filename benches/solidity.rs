@@ -1,14 +1,18 @@
 use criterion::{
-    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId, Criterion,
+    criterion_group, criterion_main, measurement::WallTime, BatchSize, BenchmarkGroup, BenchmarkId,
+    Criterion,
 };
 
 use alloy_dyn_abi::DynSolValue;
 use alloy_primitives::{I256, U256};
 use parity_scale_codec::Encode;
 use schlau::{
-    evm::{EvmContract, ACCOUNTS},
-    solang::SolangContract,
+    drink::runtime::MinimalRuntime,
+    drink_api::{CreateArgs, DrinkApi, InstantiateArgs},
+    evm::{EvmContract, FeeMode},
+    solang::{build_and_load_contract, SolangContract},
 };
+use subxt_signer::sr25519::dev;
 
 fn bench_evm(
     group: &mut BenchmarkGroup<WallTime>,
@@ -18,20 +22,15 @@ fn bench_evm(
 ) {
     if cfg!(feature = "evm") {
         for (args, parameter) in args {
-            let mut evm_contract = EvmContract::init(contract);
+            // no fees, so that the same caller can be used for every iteration
+            let mut evm_contract = EvmContract::init(contract).with_fee_mode(FeeMode::Zero);
 
             let id = BenchmarkId::new("evm", parameter);
             let args = evm_contract.call_args(message, &args.clone());
-            let mut account_index = 0;
 
             group.bench_function(id, |b| {
                 b.iter(|| {
-                    let mut args = args.clone();
-                    // use a different account to avoid `BalanceLow`
-                    args.source = ACCOUNTS[account_index];
-                    account_index = (account_index + 1) % ACCOUNTS.len();
-
-                    evm_contract.sandbox.call(args).unwrap();
+                    evm_contract.sandbox.call(args.clone()).unwrap();
                 })
             });
         }
@@ -122,6 +121,22 @@ fn remainders(c: &mut Criterion) {
     group.finish()
 }
 
+fn sha256(c: &mut Criterion) {
+    let ns = [2000u32, 4000, 8000].map(|n| (n, n.to_string()));
+    let ns_evm = ns
+        .clone()
+        .map(|(n, display)| (vec![DynSolValue::Uint(U256::from(n), 32)], display))
+        .to_vec();
+
+    let mut group = c.benchmark_group("sha256");
+    group.sample_size(20);
+
+    bench_evm(&mut group, "Crypto", "sha256_chain", &ns_evm);
+    bench_solang(&mut group, "Crypto", "sha256_chain", &ns);
+
+    group.finish()
+}
+
 fn baseline(c: &mut Criterion) {
     let args_scale = [(0, "(0)".to_owned())];
     let args_evm = [(vec![DynSolValue::Uint(U256::ZERO, 32)], "(1)".to_owned())];
@@ -163,6 +178,58 @@ fn fibonacci(c: &mut Criterion) {
     group.finish();
 }
 
+/// Compare uploading code, instantiating a contract from uploaded code, and both at once. Every
+/// iteration starts from a fresh sandbox, so the code is never already uploaded.
+fn instantiate(c: &mut Criterion) {
+    let build_result = build_and_load_contract("contracts/solidity/Computation.sol").unwrap();
+    let data = build_result.constructor_selector("new").unwrap();
+    let code = build_result.code;
+
+    let mut group = c.benchmark_group("instantiate");
+    group.sample_size(20);
+
+    let id = |parameter| BenchmarkId::new(format!("solang({})", schlau::target_str()), parameter);
+    group.bench_function(id("upload"), |b| {
+        b.iter_batched_ref(
+            DrinkApi::<MinimalRuntime>::new,
+            |drink_api| drink_api.upload_code(code.clone(), dev::alice()).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function(id("instantiate"), |b| {
+        b.iter_batched_ref(
+            || {
+                let mut drink_api = DrinkApi::<MinimalRuntime>::new();
+                let code_hash = drink_api.upload_code(code.clone(), dev::alice()).unwrap();
+                let instantiate_args =
+                    InstantiateArgs::new(code_hash, dev::alice()).with_data(data.clone());
+                (drink_api, instantiate_args)
+            },
+            |(drink_api, instantiate_args)| {
+                drink_api.instantiate(instantiate_args.clone()).unwrap()
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function(id("upload_and_instantiate"), |b| {
+        b.iter_batched_ref(
+            || {
+                let create_args =
+                    CreateArgs::new(code.clone(), dev::alice()).with_data(data.clone());
+                (DrinkApi::<MinimalRuntime>::new(), create_args)
+            },
+            |(drink_api, create_args)| {
+                drink_api
+                    .instantiate_with_code(create_args.clone())
+                    .unwrap()
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.finish()
+}
+
 criterion_group!(
     computation,
     baseline,
@@ -171,5 +238,8 @@ criterion_group!(
     fibonacci
 );
 criterion_group!(arithmetics, remainders);
+criterion_group!(crypto, sha256);
+
+criterion_group!(deployment, instantiate);
 
-criterion_main!(computation, arithmetics);
+criterion_main!(computation, arithmetics, crypto, deployment);
@@ -0,0 +1,76 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use ink::env::{DefaultEnvironment, Environment};
+
+/// The id of the [`Echo`] chain extension.
+pub const ECHO_EXTENSION_ID: u16 = 1;
+/// The id of the [`Echo::echo`] function.
+pub const ECHO_FUNCTION_ID: u16 = 1;
+
+/// A chain extension returning its input unchanged, to measure the overhead of a chain
+/// extension call.
+#[ink::chain_extension(extension = 1)]
+pub trait Echo {
+    type ErrorCode = EchoError;
+
+    #[ink(function = 1, handle_status = false)]
+    fn echo(input: ink::prelude::vec::Vec<u8>) -> ink::prelude::vec::Vec<u8>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum EchoError {
+    Failed,
+}
+
+impl ink::env::chain_extension::FromStatusCode for EchoError {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            _ => Err(Self::Failed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum EchoEnvironment {}
+
+impl Environment for EchoEnvironment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <DefaultEnvironment as Environment>::AccountId;
+    type Balance = <DefaultEnvironment as Environment>::Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+
+    type ChainExtension = Echo;
+}
+
+#[ink::contract(env = crate::EchoEnvironment)]
+pub mod chain_ext {
+    use ink::prelude::vec;
+
+    #[ink(storage)]
+    pub struct ChainExt {}
+
+    impl ChainExt {
+        #[allow(clippy::new_without_default)]
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {}
+        }
+
+        /// Call the echo chain extension `iterations` times with a 32 byte input.
+        #[ink(message)]
+        pub fn echo(&self, iterations: u32) -> u32 {
+            let mut echoed = 0;
+            for i in 0..iterations {
+                let output = self.env().extension().echo(vec![i as u8; 32]);
+                echoed += output.len() as u32;
+            }
+            echoed
+        }
+    }
+}
@@ -0,0 +1,46 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+pub mod preload {
+    use ink::storage::Mapping;
+
+    #[ink(storage)]
+    pub struct Preload {
+        values: Mapping<u32, u32>,
+        entries: u32,
+    }
+
+    impl Preload {
+        #[allow(clippy::new_without_default)]
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::with_entries(0)
+        }
+
+        /// Preload the mapping with `entries` values.
+        #[ink(constructor, payable)]
+        pub fn with_entries(entries: u32) -> Self {
+            let mut values = Mapping::default();
+            for key in 0..entries {
+                values.insert(key, &key.wrapping_mul(2));
+            }
+            Self { values, entries }
+        }
+
+        #[ink(message)]
+        pub fn entries(&self) -> u32 {
+            self.entries
+        }
+
+        /// Sum `reads` values, cycling through the preloaded keys.
+        #[ink(message)]
+        pub fn sum(&self, reads: u32) -> u32 {
+            let mut sum = 0u32;
+            for i in 0..reads {
+                let key = i % self.entries.max(1);
+                sum = sum.wrapping_add(self.values.get(key).unwrap_or_default());
+            }
+            sum
+        }
+    }
+}
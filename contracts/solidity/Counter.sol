@@ -0,0 +1,7 @@
+contract Counter {
+    uint256 public count;
+
+    function increment(uint32 n) public {
+        count += n;
+    }
+}
@@ -0,0 +1,7 @@
+contract Crypto {
+    function sha256_chain(uint32 iterations) public pure returns (bytes32 hash) {
+        for (uint32 i = 0; i < iterations; i++) {
+            hash = sha256(abi.encodePacked(hash));
+        }
+    }
+}
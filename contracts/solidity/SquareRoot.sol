@@ -0,0 +1,11 @@
+import "@schlau/Math.sol";
+
+contract SquareRoot {
+    function sqrt_sum(uint32 n) public pure returns (uint64) {
+        uint64 sum = 0;
+        for (uint64 i = 0; i < n; i++) {
+            sum += Math.sqrt(i);
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,12 @@
+library Math {
+    /// Integer square root, rounded down, using Newton's method.
+    function sqrt(uint64 x) internal pure returns (uint64) {
+        uint64 y = x;
+        uint64 z = x / 2 + (x & 1);
+        while (z < y) {
+            y = z;
+            z = (x / z + z) / 2;
+        }
+        return y;
+    }
+}
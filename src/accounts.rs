@@ -0,0 +1,152 @@
+//! Deterministically derived accounts for multi-user workloads.
+//!
+//! [`Accounts`] derives any number of sr25519 keypairs, for the contracts sandbox, and secp256k1
+//! keypairs with their Ethereum address, for the EVM sandbox, from a seed phrase. The same seed and
+//! index always yield the same accounts, so benchmarks are reproducible.
+
+use alloy_primitives::keccak256;
+use sp_core::{ecdsa, Pair, H160};
+use subxt_signer::{sr25519::Keypair, SecretUri};
+
+/// The balance each account is funded with by default.
+pub const DEFAULT_BALANCE: u128 = 1_000_000_000_000_000;
+
+/// A secp256k1 keypair and the Ethereum address derived from its public key.
+#[derive(Clone)]
+pub struct EvmAccount {
+    pub pair: ecdsa::Pair,
+    pub address: H160,
+}
+
+impl EvmAccount {
+    fn from_pair(pair: ecdsa::Pair) -> anyhow::Result<Self> {
+        let public = k256::ecdsa::VerifyingKey::from_sec1_bytes(pair.public().as_ref())?;
+        let uncompressed = public.to_encoded_point(false);
+        // the address is the last 20 bytes of the hash of the public key, without the tag byte
+        let hash = keccak256(&uncompressed.as_bytes()[1..]);
+        let address = H160::from_slice(&hash[12..]);
+        Ok(Self { pair, address })
+    }
+}
+
+/// Accounts derived from a seed, with the balance each should be funded with.
+///
+/// Accessors take the index modulo the number of accounts, so workloads can hand out accounts
+/// round robin with an ever increasing counter.
+#[derive(Clone)]
+pub struct Accounts {
+    keypairs: Vec<Keypair>,
+    evm_accounts: Vec<EvmAccount>,
+    balances: Vec<u128>,
+}
+
+impl Accounts {
+    /// Derive `count` accounts from `seed`, using the hard derivation path `//{seed}//{index}`.
+    pub fn new(seed: &str, count: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(count > 0, "At least one account is required");
+
+        let mut keypairs = Vec::with_capacity(count);
+        let mut evm_accounts = Vec::with_capacity(count);
+        for index in 0..count {
+            let uri = format!("//{seed}//{index}");
+
+            let secret_uri = uri.parse::<SecretUri>()?;
+            keypairs.push(Keypair::from_uri(&secret_uri)?);
+
+            let (pair, _) = ecdsa::Pair::from_string_with_seed(&uri, None)
+                .map_err(|err| anyhow::anyhow!("Error deriving {uri}: {err:?}"))?;
+            evm_accounts.push(EvmAccount::from_pair(pair)?);
+        }
+
+        Ok(Self {
+            keypairs,
+            evm_accounts,
+            balances: vec![DEFAULT_BALANCE; count],
+        })
+    }
+
+    /// Fund all accounts with `balance`.
+    pub fn with_balance(mut self, balance: u128) -> Self {
+        self.balances.iter_mut().for_each(|b| *b = balance);
+        self
+    }
+
+    /// Fund the account at `index` with `balance`.
+    pub fn with_balance_of(mut self, index: usize, balance: u128) -> Self {
+        let index = index % self.len();
+        self.balances[index] = balance;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.keypairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keypairs.is_empty()
+    }
+
+    /// The sr25519 keypair of the account at `index`.
+    pub fn keypair(&self, index: usize) -> Keypair {
+        self.keypairs[index % self.len()].clone()
+    }
+
+    /// The contracts account id of the account at `index`.
+    pub fn account_id<AccountId: From<[u8; 32]>>(&self, index: usize) -> AccountId {
+        AccountId::from(self.keypair(index).public_key().0)
+    }
+
+    /// The secp256k1 keypair and address of the account at `index`.
+    pub fn evm_account(&self, index: usize) -> &EvmAccount {
+        &self.evm_accounts[index % self.len()]
+    }
+
+    /// The Ethereum address of the account at `index`.
+    pub fn evm_address(&self, index: usize) -> H160 {
+        self.evm_account(index).address
+    }
+
+    /// The balance the account at `index` is funded with.
+    pub fn balance(&self, index: usize) -> u128 {
+        self.balances[index % self.len()]
+    }
+
+    /// The contracts account ids with their balances.
+    pub fn funded_account_ids<AccountId: From<[u8; 32]>>(
+        &self,
+    ) -> impl Iterator<Item = (AccountId, u128)> + '_ {
+        (0..self.len()).map(|index| (self.account_id(index), self.balance(index)))
+    }
+
+    /// The Ethereum addresses with their balances.
+    pub fn funded_evm_addresses(&self) -> impl Iterator<Item = (H160, u128)> + '_ {
+        (0..self.len()).map(|index| (self.evm_address(index), self.balance(index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_distinct_accounts_deterministically() {
+        let accounts = Accounts::new("schlau", 4).unwrap().with_balance_of(1, 42);
+        let again = Accounts::new("schlau", 4).unwrap();
+
+        for index in 0..4 {
+            assert_eq!(accounts.evm_address(index), again.evm_address(index));
+            assert_eq!(
+                accounts.account_id::<[u8; 32]>(index),
+                again.account_id::<[u8; 32]>(index)
+            );
+        }
+        assert_ne!(accounts.evm_address(0), accounts.evm_address(1));
+        assert_ne!(
+            accounts.account_id::<[u8; 32]>(0),
+            accounts.account_id::<[u8; 32]>(1)
+        );
+        assert_eq!(accounts.evm_address(4), accounts.evm_address(0));
+        assert_eq!(accounts.balance(1), 42);
+        assert_eq!(accounts.balance(2), DEFAULT_BALANCE);
+    }
+}
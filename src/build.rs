@@ -0,0 +1,328 @@
+//! Building all benchmark contracts up front, before any measurement starts.
+//!
+//! [`BuildAll`] discovers the ink! contracts under `contracts/ink/*` and the Solidity sources
+//! under `contracts/solidity/*.sol`, and compiles them for all targets enabled by the crate
+//! features in parallel, so compile errors are reported before any measurement starts.
+//!
+//! This does not save the benchmarks any work: they still build their contracts during setup.
+//! ink! contracts are rebuilt incrementally by cargo, but `solc` and `solang` do no caching, so
+//! Solidity sources are compiled again.
+//!
+//! All targets of one contract are built by the same worker one after another, since the ink!
+//! builds of all targets share the `target` directory of the contract. Solidity builds are written
+//! to a separate directory per target, see [`crate::solang::out_dir`].
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use contract_build::Target;
+
+use crate::diagnostics::CompileError;
+
+/// The directory containing the benchmark contracts.
+pub const CONTRACTS_DIR: &str = "contracts";
+
+/// A compiler for one target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compiler {
+    /// `cargo contract` via `contract_build`, for ink! contracts.
+    Ink(Target),
+    /// `solang`, compiling Solidity for `pallet-contracts`.
+    Solang(Target),
+    /// `solc`, compiling Solidity for the EVM.
+    Solc,
+}
+
+impl fmt::Display for Compiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compiler::Ink(target) => write!(f, "ink({})", target_str(*target)),
+            Compiler::Solang(target) => write!(f, "solang({})", target_str(*target)),
+            Compiler::Solc => write!(f, "solc"),
+        }
+    }
+}
+
+fn target_str(target: Target) -> &'static str {
+    match target {
+        Target::Wasm => "wasm",
+        Target::RiscV => "riscv",
+    }
+}
+
+/// A contract source, i.e. the `Cargo.toml` of an ink! contract or a Solidity file, and the
+/// compilers to build it with.
+#[derive(Clone, Debug)]
+pub struct BuildJob {
+    pub path: PathBuf,
+    pub compilers: Vec<Compiler>,
+}
+
+impl BuildJob {
+    fn build(&self, compiler: Compiler) -> anyhow::Result<()> {
+        match compiler {
+            Compiler::Ink(target) => crate::ink::build_contract(&self.path, target).map(|_| ()),
+            Compiler::Solang(target) => {
+                crate::solang::build_contract(&self.path, target).map(|_| ())
+            }
+            Compiler::Solc => crate::solc::build_contract(&self.path).map(|_| ()),
+        }
+    }
+}
+
+/// The outcome of building one contract with one compiler.
+#[derive(Debug)]
+pub struct Built {
+    pub path: PathBuf,
+    pub compiler: Compiler,
+    pub duration: Duration,
+}
+
+/// Build all benchmark contracts in parallel.
+pub struct BuildAll {
+    contracts_dir: PathBuf,
+    targets: Vec<Target>,
+    evm: bool,
+    jobs: usize,
+    progress: bool,
+}
+
+impl Default for BuildAll {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildAll {
+    /// Build the contracts in [`CONTRACTS_DIR`] for all targets enabled by the crate features,
+    /// with one worker per available CPU.
+    pub fn new() -> Self {
+        let mut targets = Vec::new();
+        if cfg!(feature = "wasm") {
+            targets.push(Target::Wasm);
+        }
+        if cfg!(feature = "riscv") {
+            targets.push(Target::RiscV);
+        }
+        Self {
+            contracts_dir: PathBuf::from(CONTRACTS_DIR),
+            targets,
+            evm: cfg!(feature = "evm"),
+            jobs: thread::available_parallelism().map_or(1, Into::into),
+            progress: true,
+        }
+    }
+
+    pub fn with_contracts_dir<P: AsRef<Path>>(mut self, contracts_dir: P) -> Self {
+        self.contracts_dir = contracts_dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Build for `targets` instead of the targets enabled by the crate features.
+    pub fn with_targets(mut self, targets: Vec<Target>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// Whether to build the Solidity contracts for the EVM with `solc`.
+    pub fn with_evm(mut self, evm: bool) -> Self {
+        self.evm = evm;
+        self
+    }
+
+    /// The number of contracts to build in parallel.
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Whether to print the progress to stderr.
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Discover the contracts to build, with the compilers to build each of them with.
+    pub fn discover(&self) -> anyhow::Result<Vec<BuildJob>> {
+        let mut jobs = Vec::new();
+
+        let ink_dir = self.contracts_dir.join("ink");
+        if !self.targets.is_empty() && ink_dir.is_dir() {
+            for manifest in sorted_entries(&ink_dir)?
+                .into_iter()
+                .map(|dir| dir.join("Cargo.toml"))
+                .filter(|manifest| manifest.is_file())
+            {
+                jobs.push(BuildJob {
+                    path: manifest,
+                    compilers: self.targets.iter().copied().map(Compiler::Ink).collect(),
+                });
+            }
+        }
+
+        let solidity_dir = self.contracts_dir.join("solidity");
+        if solidity_dir.is_dir() {
+            let mut compilers = self
+                .targets
+                .iter()
+                .copied()
+                .map(Compiler::Solang)
+                .collect::<Vec<_>>();
+            if self.evm {
+                compilers.push(Compiler::Solc);
+            }
+            if !compilers.is_empty() {
+                for source in sorted_entries(&solidity_dir)?
+                    .into_iter()
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "sol"))
+                {
+                    jobs.push(BuildJob {
+                        path: source,
+                        compilers: compilers.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    /// Build all discovered contracts.
+    ///
+    /// Fails fast: once a build fails no further builds are started, and the error lists every
+    /// failed build with its diagnostics.
+    pub fn run(&self) -> anyhow::Result<Vec<Built>> {
+        let jobs = self.discover()?;
+        let total = jobs.iter().map(|job| job.compilers.len()).sum::<usize>();
+        anyhow::ensure!(
+            total > 0,
+            "No contracts to build in {}, is any target feature enabled?",
+            self.contracts_dir.display()
+        );
+
+        let queue = Mutex::new(jobs.into_iter().collect::<VecDeque<_>>());
+        let done = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let built = Mutex::new(Vec::new());
+        let failures = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            for _ in 0..self.jobs {
+                scope.spawn(|| {
+                    while !failed.load(Ordering::Relaxed) {
+                        let Some(job) = queue.lock().expect("poisoned queue").pop_front() else {
+                            break;
+                        };
+                        for &compiler in &job.compilers {
+                            if failed.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            let start = Instant::now();
+                            let result = job.build(compiler);
+                            let duration = start.elapsed();
+                            let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+                            match result {
+                                Ok(()) => {
+                                    self.report(format_args!(
+                                        "[{count}/{total}] Built {} with {compiler} in {:.1}s",
+                                        job.path.display(),
+                                        duration.as_secs_f64()
+                                    ));
+                                    built.lock().expect("poisoned results").push(Built {
+                                        path: job.path.clone(),
+                                        compiler,
+                                        duration,
+                                    });
+                                }
+                                Err(err) => {
+                                    failed.store(true, Ordering::Relaxed);
+                                    self.report(format_args!(
+                                        "[{count}/{total}] Failed to build {} with {compiler}",
+                                        job.path.display()
+                                    ));
+                                    let failure = match err.downcast_ref::<CompileError>() {
+                                        Some(compile_error) => compile_error.to_string(),
+                                        None => format!(
+                                            "{} with {compiler}: {err:?}",
+                                            job.path.display()
+                                        ),
+                                    };
+                                    failures.lock().expect("poisoned failures").push(failure);
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let failures = failures.into_inner().expect("poisoned failures");
+        if !failures.is_empty() {
+            anyhow::bail!(
+                "Failed to build {} contract(s):\n\n{}",
+                failures.len(),
+                failures.join("\n\n")
+            );
+        }
+        Ok(built.into_inner().expect("poisoned results"))
+    }
+
+    fn report(&self, message: fmt::Arguments) {
+        if self.progress {
+            eprintln!("{message}");
+        }
+    }
+}
+
+/// The entries of `dir`, sorted by path so the build order is stable.
+fn sorted_entries(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_benchmark_contracts() {
+        let build_all = BuildAll::new()
+            .with_targets(vec![Target::Wasm, Target::RiscV])
+            .with_evm(true);
+        let jobs = build_all.discover().unwrap();
+
+        let crypto = jobs
+            .iter()
+            .find(|job| job.path == Path::new("contracts/ink/crypto/Cargo.toml"))
+            .expect("ink! contract should be discovered");
+        assert_eq!(
+            crypto.compilers,
+            [Compiler::Ink(Target::Wasm), Compiler::Ink(Target::RiscV)]
+        );
+
+        let computation = jobs
+            .iter()
+            .find(|job| job.path == Path::new("contracts/solidity/Computation.sol"))
+            .expect("Solidity contract should be discovered");
+        assert_eq!(
+            computation.compilers,
+            [
+                Compiler::Solang(Target::Wasm),
+                Compiler::Solang(Target::RiscV),
+                Compiler::Solc
+            ]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Chain extensions of the [`super::ContractsRuntime`].
+//!
+//! Extensions are implemented in Rust and registered with [`register_chain_extension`] under the
+//! extension and function id that the calling contract uses, e.g.
+//! `#[ink::chain_extension(extension = 1)]` and `#[ink(function = 1)]`.
+
+use std::rc::Rc;
+
+use crate::{
+    drink::{
+        frame_support::sp_runtime::DispatchError,
+        pallet_contracts::chain_extension::{
+            ChainExtension, Environment, Ext, InitState, Result, RetVal,
+        },
+    },
+    registry::Registry,
+};
+
+use super::ContractsRuntime;
+
+/// A chain extension function implemented by a closure, mapping the raw input of the contract to
+/// the raw output written back to the contract.
+pub type CustomChainExtension = Rc<dyn Fn(&[u8]) -> Result<Vec<u8>>>;
+
+thread_local! {
+    static CHAIN_EXTENSIONS: Registry<(u16, u16), CustomChainExtension> =
+        const { Registry::new() };
+}
+
+/// Register a chain extension function, replacing any existing function with the same ids.
+///
+/// Chain extensions are registered for all sandboxes on the current thread.
+pub fn register_chain_extension<F>(extension_id: u16, func_id: u16, extension: F)
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>> + 'static,
+{
+    CHAIN_EXTENSIONS
+        .with(|extensions| extensions.insert((extension_id, func_id), Rc::new(extension)));
+}
+
+/// Remove the chain extension function with the given ids, if any.
+pub fn unregister_chain_extension(extension_id: u16, func_id: u16) {
+    CHAIN_EXTENSIONS.with(|extensions| extensions.remove(&(extension_id, func_id)));
+}
+
+pub(crate) fn chain_extension(extension_id: u16, func_id: u16) -> Option<CustomChainExtension> {
+    CHAIN_EXTENSIONS.with(|extensions| extensions.get(&(extension_id, func_id)))
+}
+
+/// Dispatches chain extension calls to the registered functions.
+///
+/// Only the weight of the `call_chain_extension` host function is charged, so benchmarks measure
+/// the overhead of the call itself.
+#[derive(Default)]
+pub struct ChainExtensions;
+
+impl ChainExtension<ContractsRuntime> for ChainExtensions {
+    fn call<E: Ext<T = ContractsRuntime>>(
+        &mut self,
+        env: Environment<E, InitState>,
+    ) -> Result<RetVal> {
+        let (extension_id, func_id) = (env.ext_id(), env.func_id());
+        let extension = chain_extension(extension_id, func_id)
+            .ok_or(DispatchError::Other("Unregistered chain extension"))?;
+
+        let mut env = env.buf_in_buf_out();
+        let input = env.read(env.in_len())?;
+        let output = extension(&input)?;
+        env.write(&output, false, None)?;
+
+        Ok(RetVal::Converging(0))
+    }
+}
@@ -0,0 +1,123 @@
+//! A `pallet-contracts` runtime owned by schlau, as an alternative to `drink`'s `MinimalRuntime`.
+//!
+//! The `Schedule`, storage deposits, code size limit and call stack depth of the
+//! [`ContractsRuntime`] are kept in storage, so they can be configured per sandbox via
+//! [`ContractsConfig`], e.g. to benchmark under the configuration of a production parachain. Chain
+//! extensions can be registered with [`chain_extension::register_chain_extension`].
+
+pub mod chain_extension;
+mod runtime;
+
+use crate::{drink::pallet_contracts::Schedule, drink_api::DrinkApi};
+
+pub use runtime::{Balance, CallStack, ContractsRuntime, DEFAULT_ACTOR, MAX_CALL_STACK_DEPTH};
+use runtime::{
+    CallStackDepth, ContractsSchedule, DefaultDepositLimit, DepositPerByte, DepositPerItem,
+    MaxCodeLen,
+};
+
+/// The parameters of the [`ContractsRuntime`].
+#[derive(Clone)]
+pub struct ContractsConfig {
+    /// The cost schedule and limits of contract execution.
+    pub schedule: Schedule<ContractsRuntime>,
+    /// The storage deposit charged per byte of contract storage.
+    pub deposit_per_byte: Balance,
+    /// The storage deposit charged per contract storage item.
+    pub deposit_per_item: Balance,
+    /// The storage deposit limit used when none is provided with a call.
+    pub default_deposit_limit: Balance,
+    /// The maximum size of contract code in bytes.
+    pub max_code_len: u32,
+    /// The maximum number of nested contract calls, capped to [`MAX_CALL_STACK_DEPTH`].
+    pub call_stack_depth: u32,
+}
+
+impl Default for ContractsConfig {
+    fn default() -> Self {
+        Self {
+            schedule: Default::default(),
+            deposit_per_byte: 1,
+            deposit_per_item: 1,
+            default_deposit_limit: 10_000_000,
+            max_code_len: 123 * 1024,
+            call_stack_depth: 5,
+        }
+    }
+}
+
+impl ContractsConfig {
+    /// Read the parameters from storage. Must be called within externalities.
+    pub fn load() -> Self {
+        Self {
+            schedule: ContractsSchedule::get(),
+            deposit_per_byte: DepositPerByte::get(),
+            deposit_per_item: DepositPerItem::get(),
+            default_deposit_limit: DefaultDepositLimit::get(),
+            max_code_len: MaxCodeLen::get(),
+            call_stack_depth: CallStackDepth::get(),
+        }
+    }
+
+    /// Write the parameters to storage. Must be called within externalities.
+    pub fn store(&self) {
+        ContractsSchedule::set(&self.schedule);
+        DepositPerByte::set(&self.deposit_per_byte);
+        DepositPerItem::set(&self.deposit_per_item);
+        DefaultDepositLimit::set(&self.default_deposit_limit);
+        MaxCodeLen::set(&self.max_code_len);
+        CallStackDepth::set(&self.call_stack_depth);
+    }
+
+    pub fn with_schedule(mut self, schedule: Schedule<ContractsRuntime>) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn with_deposit_per_byte(mut self, deposit_per_byte: Balance) -> Self {
+        self.deposit_per_byte = deposit_per_byte;
+        self
+    }
+
+    pub fn with_deposit_per_item(mut self, deposit_per_item: Balance) -> Self {
+        self.deposit_per_item = deposit_per_item;
+        self
+    }
+
+    pub fn with_default_deposit_limit(mut self, default_deposit_limit: Balance) -> Self {
+        self.default_deposit_limit = default_deposit_limit;
+        self
+    }
+
+    pub fn with_max_code_len(mut self, max_code_len: u32) -> Self {
+        self.max_code_len = max_code_len;
+        self
+    }
+
+    /// Set the maximum number of nested contract calls.
+    ///
+    /// # Panics
+    ///
+    /// If `call_stack_depth` exceeds [`MAX_CALL_STACK_DEPTH`].
+    pub fn with_call_stack_depth(mut self, call_stack_depth: u32) -> Self {
+        assert!(
+            call_stack_depth as usize <= MAX_CALL_STACK_DEPTH,
+            "The call stack depth is limited to {MAX_CALL_STACK_DEPTH}"
+        );
+        self.call_stack_depth = call_stack_depth;
+        self
+    }
+}
+
+impl DrinkApi<ContractsRuntime> {
+    /// Set the runtime parameters of the sandbox.
+    pub fn with_config(mut self, config: ContractsConfig) -> Self {
+        self.execute_with(|| config.store());
+        self
+    }
+
+    /// The current runtime parameters of the sandbox.
+    pub fn config(&mut self) -> ContractsConfig {
+        self.execute_with(ContractsConfig::load)
+    }
+}
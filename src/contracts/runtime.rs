@@ -0,0 +1,203 @@
+use crate::drink::{
+    frame_support::{
+        self, construct_runtime, parameter_types,
+        sp_runtime::{
+            testing::H256,
+            traits::{Convert, IdentityLookup},
+            AccountId32, BuildStorage, Perbill,
+        },
+        traits::{ConstBool, ConstU128, ConstU32, ConstU64, Hooks, Randomness},
+        weights::Weight,
+    },
+    frame_system::{self, pallet_prelude::BlockNumberFor, EnsureSigned},
+    pallet_balances, pallet_contracts, pallet_timestamp,
+    runtime::{AccountIdFor, Runtime as RuntimeT},
+};
+use pallet_contracts::{Frame, Schedule};
+
+/// The upper bound of the configurable call stack depth, i.e. the number of nested contract calls.
+pub const MAX_CALL_STACK_DEPTH: usize = 32;
+
+pub type Balance = u128;
+
+construct_runtime!(
+    pub enum ContractsRuntime {
+        System: frame_system,
+        Balances: pallet_balances,
+        Timestamp: pallet_timestamp,
+        Contracts: pallet_contracts,
+    }
+);
+
+impl frame_system::Config for ContractsRuntime {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type Block = frame_system::mocking::MockBlockU32<ContractsRuntime>;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type RuntimeTask = RuntimeTask;
+    type Nonce = u64;
+    type Hash = H256;
+    type Hashing = frame_support::sp_runtime::traits::BlakeTwo256;
+    type AccountId = AccountId32;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU32<250>;
+    type DbWeight = ();
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for ContractsRuntime {
+    type RuntimeEvent = RuntimeEvent;
+    type WeightInfo = ();
+    type Balance = Balance;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type ReserveIdentifier = [u8; 8];
+    type FreezeIdentifier = ();
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type MaxHolds = ConstU32<1>;
+    type MaxFreezes = ();
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type RuntimeFreezeReason = RuntimeFreezeReason;
+}
+
+impl pallet_timestamp::Config for ContractsRuntime {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<1>;
+    type WeightInfo = ();
+}
+
+/// Deterministic randomness, so that benchmarks are reproducible.
+pub struct SandboxRandomness;
+impl Randomness<H256, BlockNumberFor<ContractsRuntime>> for SandboxRandomness {
+    fn random(_subject: &[u8]) -> (H256, BlockNumberFor<ContractsRuntime>) {
+        (Default::default(), Default::default())
+    }
+}
+
+impl Convert<Weight, Balance> for ContractsRuntime {
+    fn convert(w: Weight) -> Balance {
+        w.ref_time().into()
+    }
+}
+
+parameter_types! {
+    pub storage ContractsSchedule: Schedule<ContractsRuntime> = Default::default();
+    pub storage DepositPerByte: Balance = 1;
+    pub storage DepositPerItem: Balance = 1;
+    pub storage DefaultDepositLimit: Balance = 10_000_000;
+    pub storage MaxCodeLen: u32 = 123 * 1024;
+    pub storage CallStackDepth: u32 = 5;
+    pub CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(0);
+}
+
+impl pallet_contracts::Config for ContractsRuntime {
+    type Time = Timestamp;
+    type Randomness = SandboxRandomness;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type CallFilter = ();
+    type WeightPrice = Self;
+    type WeightInfo = ();
+    type ChainExtension = super::chain_extension::ChainExtensions;
+    type Schedule = ContractsSchedule;
+    type CallStack = CallStack;
+    type DepositPerByte = DepositPerByte;
+    type DepositPerItem = DepositPerItem;
+    type DefaultDepositLimit = DefaultDepositLimit;
+    type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+    type MaxCodeLen = MaxCodeLen;
+    type MaxStorageKeyLen = ConstU32<128>;
+    type UnsafeUnstableInterface = ConstBool<false>;
+    type UploadOrigin = EnsureSigned<AccountIdFor<Self>>;
+    type InstantiateOrigin = EnsureSigned<AccountIdFor<Self>>;
+    type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type Migrations = ();
+    type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
+    type MaxDelegateDependencies = ConstU32<32>;
+    type Debug = crate::drink::runtime::pallet_contracts_debugging::DrinkDebug;
+    type Environment = ();
+    type Xcm = ();
+}
+
+/// The call stack of the [`ContractsRuntime`], whose depth is read from storage, so it can be
+/// configured per sandbox like the other parameters.
+///
+/// `construct_runtime!` does not support generic runtimes, so the depth can not be a const
+/// parameter. Instead the frames are stored inline in an array of [`MAX_CALL_STACK_DEPTH`], of
+/// which only the configured depth is used.
+#[repr(transparent)]
+pub struct CallStack([Frame<ContractsRuntime>; MAX_CALL_STACK_DEPTH]);
+
+// SAFETY: `SmallVec` uses at most `size()` items of the inline array, which is capped to its
+// length. The depth is only written between calls, so it is constant for the lifetime of a stack.
+unsafe impl smallvec::Array for CallStack {
+    type Item = Frame<ContractsRuntime>;
+
+    fn size() -> usize {
+        (CallStackDepth::get() as usize).min(MAX_CALL_STACK_DEPTH)
+    }
+}
+
+/// The account funded at genesis, and used by default to sign calls.
+pub const DEFAULT_ACTOR: AccountId32 = AccountId32::new([1u8; 32]);
+const INITIAL_BALANCE: Balance = 1_000_000_000_000_000;
+
+impl RuntimeT for ContractsRuntime {
+    fn initialize_storage(storage: &mut frame_support::sp_runtime::Storage) -> Result<(), String> {
+        pallet_balances::GenesisConfig::<Self> {
+            balances: vec![(DEFAULT_ACTOR, INITIAL_BALANCE)],
+        }
+        .assimilate_storage(storage)
+    }
+
+    fn initialize_block(
+        height: BlockNumberFor<Self>,
+        parent_hash: <Self as frame_system::Config>::Hash,
+    ) -> Result<(), String> {
+        System::reset_events();
+        System::initialize(&height, &parent_hash, &Default::default());
+
+        Balances::on_initialize(height);
+        Timestamp::set_timestamp(System::block_number() as u64 * 1_000);
+        Timestamp::on_initialize(height);
+        Contracts::on_initialize(height);
+
+        System::note_finished_initialize();
+        Ok(())
+    }
+
+    fn finalize_block(height: BlockNumberFor<Self>) -> Result<H256, String> {
+        Contracts::on_finalize(height);
+        Timestamp::on_finalize(height);
+        Balances::on_finalize(height);
+        Ok(System::finalize().hash())
+    }
+
+    fn default_actor() -> AccountIdFor<Self> {
+        DEFAULT_ACTOR
+    }
+
+    fn get_metadata() -> frame_support::__private::metadata::RuntimeMetadataPrefixed {
+        Self::metadata()
+    }
+
+    fn convert_account_to_origin(account: AccountIdFor<Self>) -> RuntimeOrigin {
+        Some(account).into()
+    }
+}
@@ -0,0 +1,27 @@
+//! The economic cost of a call, comparable between `pallet-contracts` and `pallet-evm`.
+
+/// The balance changes caused by a call, in the smallest unit of the native currency.
+///
+/// Positive values are increases of the respective balance, negative values decreases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallCost {
+    /// The storage deposit charged from the caller, or refunded to it if negative. Always zero
+    /// for `pallet-evm`, which has no storage deposits.
+    pub storage_deposit: i128,
+    /// The change of the free balance of the caller, including fees, deposits and value
+    /// transferred.
+    pub caller_balance: i128,
+    /// The change of the free balance of the called contract.
+    pub contract_balance: i128,
+}
+
+impl CallCost {
+    /// The signed difference of a balance before and after a call.
+    pub fn delta(before: u128, after: u128) -> i128 {
+        if after >= before {
+            (after - before) as i128
+        } else {
+            -((before - after) as i128)
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Structured diagnostics from Solidity compiler failures.
+//!
+//! Both `solc` and `solang` report errors in the `errors` array of the Solidity standard JSON
+//! output. [`Diagnostic::from_standard_json`] parses them, resolving the byte offsets of the source
+//! location to lines and columns. A failed build returns a [`CompileError`], which can be
+//! recovered from the `anyhow::Error` with `downcast_ref`.
+
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::toolchain::Tool;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+            Severity::Info => f.write_str("info"),
+        }
+    }
+}
+
+/// A single compiler error or warning.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    /// The 1-based line of the start of the source location.
+    pub line: Option<usize>,
+    /// The 1-based column of the start of the source location.
+    pub column: Option<usize>,
+}
+
+/// An entry of the `errors` array of the standard JSON output.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StandardJsonError {
+    severity: Severity,
+    message: String,
+    source_location: Option<SourceLocation>,
+}
+
+#[derive(Deserialize)]
+struct SourceLocation {
+    file: PathBuf,
+    start: i64,
+}
+
+impl Diagnostic {
+    /// Parse the `errors` of the standard JSON `output` of `solc` or `solang`. Source files are
+    /// looked up in `search_paths`, e.g. the base and include paths, to resolve line and column
+    /// numbers.
+    pub fn from_standard_json(
+        output: &serde_json::Value,
+        search_paths: &[PathBuf],
+    ) -> anyhow::Result<Vec<Self>> {
+        let Some(errors) = output.get("errors") else {
+            return Ok(Vec::new());
+        };
+        let errors: Vec<StandardJsonError> = serde_json::from_value(errors.clone())?;
+
+        let mut sources = BTreeMap::new();
+        let diagnostics = errors
+            .into_iter()
+            .map(|error| {
+                let (file, line, column) = match error.source_location {
+                    Some(location) => {
+                        let source = sources.entry(location.file.clone()).or_insert_with(|| {
+                            search_paths
+                                .iter()
+                                .find_map(|dir| fs::read_to_string(dir.join(&location.file)).ok())
+                        });
+                        let position = usize::try_from(location.start)
+                            .ok()
+                            .zip(source.as_deref())
+                            .map(|(offset, source)| line_column(source, offset));
+                        (
+                            Some(location.file),
+                            position.map(|(line, _)| line),
+                            position.map(|(_, column)| column),
+                        )
+                    }
+                    None => (None, None, None),
+                };
+                Diagnostic {
+                    severity: error.severity,
+                    message: error.message,
+                    file,
+                    line,
+                    column,
+                }
+            })
+            .collect();
+        Ok(diagnostics)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(file) = &self.file {
+            write!(f, "\n  --> {}", file.display())?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, ":{line}:{column}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The 1-based line and column of the byte `offset` into `source`, with the column counted in
+/// characters. An offset within a multi-byte character resolves to that character.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source.as_bytes()[..offset];
+    let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+    let line_start = before
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |newline| newline + 1);
+    (line, source[line_start..offset].chars().count() + 1)
+}
+
+/// A Solidity source failed to compile.
+#[derive(Debug)]
+pub struct CompileError {
+    pub compiler: Tool,
+    pub source: PathBuf,
+    /// The diagnostics reported by the compiler, empty if they could not be parsed.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The raw compiler output, shown if there are no diagnostics.
+    pub output: String,
+}
+
+impl CompileError {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to compile {} with {}",
+            self.source.display(),
+            self.compiler
+        )?;
+        if self.diagnostics.is_empty() {
+            write!(f, ":\n{}", self.output.trim_end())?;
+        }
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn parses_standard_json_errors() {
+        let output = serde_json::json!({
+            "errors": [
+                {
+                    "sourceLocation": { "file": "contracts/solidity/Counter.sol", "start": 20, "end": 25 },
+                    "type": "ParserError",
+                    "component": "general",
+                    "severity": "error",
+                    "message": "Expected ';' but got '}'",
+                    "formattedMessage": "ParserError: Expected ';' but got '}'"
+                },
+                {
+                    "component": "general",
+                    "severity": "warning",
+                    "type": "Warning",
+                    "message": "SPDX license identifier not provided in source file."
+                }
+            ]
+        });
+        let diagnostics = Diagnostic::from_standard_json(&output, &[PathBuf::from(".")]).unwrap();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(
+            diagnostics[0].file.as_deref(),
+            Some(Path::new("contracts/solidity/Counter.sol"))
+        );
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(2), Some(2))
+        );
+        assert_eq!(diagnostics[1].file, None);
+
+        let error = CompileError {
+            compiler: Tool::Solc,
+            source: PathBuf::from("contracts/solidity/Counter.sol"),
+            diagnostics,
+            output: String::new(),
+        };
+        assert_eq!(error.errors().count(), 1);
+        assert!(error
+            .to_string()
+            .contains("error: Expected ';' but got '}'\n  --> contracts/solidity/Counter.sol:2:"));
+    }
+
+    #[test]
+    fn resolves_line_and_column() {
+        assert_eq!(line_column("ab\ncd\nef", 0), (1, 1));
+        assert_eq!(line_column("ab\ncd\nef", 4), (2, 2));
+        assert_eq!(line_column("ab\ncd\nef", 6), (3, 1));
+        // `ä` and `€` take 2 and 3 bytes
+        assert_eq!(line_column("x\näb€c", 5), (2, 3));
+        assert_eq!(line_column("x\näb€c", 6), (2, 3));
+        assert_eq!(line_column("x\näb€c", 8), (2, 4));
+    }
+}
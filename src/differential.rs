@@ -0,0 +1,480 @@
+//! Differential testing of the same logical call across all backends.
+//!
+//! A [`Workload`] describes a message by its name and Solidity style signature, together with the
+//! contracts implementing it. [`Differential`] instantiates every implementation on every enabled
+//! backend: Solidity contracts on `pallet-evm` via solc and on `pallet-contracts` via Solang, and
+//! ink! contracts on `pallet-contracts`. Arguments and results are represented as [`DynSolValue`]s,
+//! encoded with the Solidity ABI for the EVM and with SCALE for `pallet-contracts`, so the results
+//! of all backends can be compared directly.
+//!
+//! All implementations of the workload are compared with each other, unless the workload allows
+//! them to diverge for specific inputs, see [`Workload::allow_divergence_on`].
+//!
+//! ```no_run
+//! # use alloy_dyn_abi::DynSolValue;
+//! # use alloy_primitives::I256;
+//! # use schlau::differential::{Differential, Workload};
+//! let workload = Workload::new("odd_product", "(int32)", "(int64)")
+//!     .unwrap()
+//!     .solidity("Computation")
+//!     .ink("computation");
+//! let mut differential = Differential::new(workload).unwrap();
+//! let args = [DynSolValue::Int(I256::try_from(5).unwrap(), 32)];
+//! differential.check(&args).unwrap();
+//! ```
+
+use std::{fmt, sync::Arc};
+
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_primitives::{I256, U256};
+use contract_build::Target;
+use parity_scale_codec::{Compact, Decode, Encode};
+use subxt_signer::sr25519::dev;
+
+use crate::{
+    drink::runtime::{AccountIdFor, MinimalRuntime},
+    drink_api::{CallArgs, CreateArgs, DrinkApi},
+    evm::EvmContract,
+    solang::SolangContract,
+};
+
+/// A message implemented by one or more contracts.
+#[derive(Debug, Clone)]
+pub struct Workload {
+    message: String,
+    inputs: Vec<DynSolType>,
+    outputs: Vec<DynSolType>,
+    solidity: Vec<String>,
+    ink: Vec<String>,
+    divergent: Option<DivergentInputs>,
+}
+
+/// The arguments for which implementations may diverge, see [`Workload::allow_divergence_on`].
+#[derive(Clone)]
+struct DivergentInputs(Arc<dyn Fn(&[DynSolValue]) -> bool + Send + Sync>);
+
+impl fmt::Debug for DivergentInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DivergentInputs(..)")
+    }
+}
+
+impl Workload {
+    /// Create a workload for `message` with the given input and output tuple types, e.g.
+    /// `Workload::new("remainders", "(uint256,uint256)", "(uint256,uint256)")`.
+    pub fn new(message: &str, inputs: &str, outputs: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            message: message.to_owned(),
+            inputs: parse_tuple(inputs)?,
+            outputs: parse_tuple(outputs)?,
+            solidity: Vec::new(),
+            ink: Vec::new(),
+            divergent: None,
+        })
+    }
+
+    /// Add an implementation by the Solidity contract `contracts/solidity/{name}.sol`.
+    pub fn solidity(mut self, name: &str) -> Self {
+        self.solidity.push(name.to_owned());
+        self
+    }
+
+    /// Add an implementation by the ink! contract `contracts/ink/{name}`.
+    pub fn ink(mut self, name: &str) -> Self {
+        self.ink.push(name.to_owned());
+        self
+    }
+
+    /// Allow different implementations to disagree for the arguments matching `inputs`, e.g.
+    /// where only some of them overflow. For these arguments each implementation is still compared
+    /// with itself across the backends.
+    pub fn allow_divergence_on<F>(mut self, inputs: F) -> Self
+    where
+        F: Fn(&[DynSolValue]) -> bool + Send + Sync + 'static,
+    {
+        self.divergent = Some(DivergentInputs(Arc::new(inputs)));
+        self
+    }
+
+    /// The types of the message arguments.
+    pub fn inputs(&self) -> &[DynSolType] {
+        &self.inputs
+    }
+
+    fn allows_divergence(&self, args: &[DynSolValue]) -> bool {
+        self.divergent
+            .as_ref()
+            .is_some_and(|divergent| (divergent.0)(args))
+    }
+}
+
+fn parse_tuple(types: &str) -> anyhow::Result<Vec<DynSolType>> {
+    match DynSolType::parse(types)? {
+        DynSolType::Tuple(types) => Ok(types),
+        ty => Err(anyhow::anyhow!("Expected a tuple type, got '{ty}'")),
+    }
+}
+
+/// A contract deployed on a specific VM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backend {
+    pub contract: String,
+    pub vm: Vm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vm {
+    /// Solidity compiled with solc, executed on `pallet-evm`.
+    Evm,
+    /// Solidity compiled with Solang, executed on `pallet-contracts`.
+    Solang(Target),
+    /// ink! executed on `pallet-contracts`.
+    Ink(Target),
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target = |target| match target {
+            Target::Wasm => "wasm",
+            Target::RiscV => "riscv",
+        };
+        match self.vm {
+            Vm::Evm => write!(f, "{} on evm", self.contract),
+            Vm::Solang(t) => write!(f, "{} on solang({})", self.contract, target(t)),
+            Vm::Ink(t) => write!(f, "{} on ink({})", self.contract, target(t)),
+        }
+    }
+}
+
+/// The outcome of a call on a single backend.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The call succeeded, returning the decoded outputs as a tuple.
+    Returned(DynSolValue),
+    /// The call failed, with the reason including its causes. Failures are considered equal
+    /// regardless of the reason, since the error representation differs between the backends,
+    /// but the reasons are part of any divergence report.
+    Failed(String),
+}
+
+impl PartialEq for Outcome {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Returned(a), Self::Returned(b)) => a == b,
+            (Self::Failed(_), Self::Failed(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Returned(value) => write!(f, "{value:?}"),
+            Self::Failed(reason) => write!(f, "failed: {reason}"),
+        }
+    }
+}
+
+enum Instance {
+    Evm(Box<EvmContract>),
+    Solang(Box<SolangContract>),
+    Ink {
+        drink_api: Box<DrinkApi<MinimalRuntime>>,
+        contract_account: AccountIdFor<MinimalRuntime>,
+    },
+}
+
+/// A [`Workload`] instantiated on all enabled backends.
+pub struct Differential {
+    workload: Workload,
+    instances: Vec<(Backend, Instance)>,
+}
+
+impl Differential {
+    /// Build and instantiate all implementations of the workload on all enabled backends.
+    pub fn new(workload: Workload) -> anyhow::Result<Self> {
+        let target = crate::target();
+        let mut instances = Vec::new();
+
+        for contract in &workload.solidity {
+            if cfg!(feature = "evm") {
+                let backend = Backend {
+                    contract: contract.clone(),
+                    vm: Vm::Evm,
+                };
+                let instance = Instance::Evm(Box::new(EvmContract::init(contract)));
+                instances.push((backend, instance));
+            }
+            let backend = Backend {
+                contract: contract.clone(),
+                vm: Vm::Solang(target),
+            };
+            let instance = Instance::Solang(Box::new(SolangContract::init(contract)));
+            instances.push((backend, instance));
+        }
+
+        for contract in &workload.ink {
+            let manifest = format!("contracts/ink/{contract}/Cargo.toml");
+            let code = std::fs::read(crate::ink::build_contract(&manifest, target)?)?;
+            let mut drink_api = DrinkApi::<MinimalRuntime>::new();
+            let create_args = CreateArgs::<MinimalRuntime>::new(code, dev::alice())
+                .with_data(ink_selector("new").to_vec());
+            let contract_account = drink_api.instantiate_with_code(create_args)?;
+            let backend = Backend {
+                contract: contract.clone(),
+                vm: Vm::Ink(target),
+            };
+            let instance = Instance::Ink {
+                drink_api: Box::new(drink_api),
+                contract_account,
+            };
+            instances.push((backend, instance));
+        }
+
+        if instances.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Workload '{}' has no implementations",
+                workload.message
+            ));
+        }
+        Ok(Self {
+            workload,
+            instances,
+        })
+    }
+
+    /// The backends the workload is instantiated on.
+    pub fn backends(&self) -> impl Iterator<Item = &Backend> {
+        self.instances.iter().map(|(backend, _)| backend)
+    }
+
+    /// Call the message with `args` on all backends.
+    pub fn call(&mut self, args: &[DynSolValue]) -> anyhow::Result<Vec<(Backend, Outcome)>> {
+        let Workload {
+            message, outputs, ..
+        } = &self.workload;
+        let scale_args = scale_encode(&DynSolValue::Tuple(args.to_vec()))?;
+
+        let mut outcomes = Vec::new();
+        for (backend, instance) in &mut self.instances {
+            let outcome = match instance {
+                Instance::Evm(contract) => {
+                    let call_args = contract.call_args(message, args);
+                    contract.sandbox.call(call_args).and_then(|data| {
+                        let ty = DynSolType::Tuple(outputs.clone());
+                        Ok(ty.abi_decode_sequence(&data)?)
+                    })
+                }
+                Instance::Solang(contract) => {
+                    let call_args = contract.call_args_encoded(message, scale_args.clone());
+                    contract
+                        .drink_api
+                        .call(call_args)
+                        .and_then(|data| scale_decode_outputs(outputs, &data))
+                }
+                Instance::Ink {
+                    drink_api,
+                    contract_account,
+                } => {
+                    let mut input = ink_selector(message).to_vec();
+                    input.extend_from_slice(&scale_args);
+                    let call_args = CallArgs::<MinimalRuntime>::new(
+                        contract_account.clone(),
+                        dev::alice(),
+                        input,
+                    )
+                    .with_max_gas_limit();
+                    drink_api
+                        .call(call_args)
+                        .and_then(|data| match data.split_first() {
+                            // the message result is wrapped in `Result<_, LangError>`
+                            Some((0, data)) => scale_decode_outputs(outputs, data),
+                            _ => Err(anyhow::anyhow!("Message returned a LangError")),
+                        })
+                }
+            };
+            let outcome = match outcome {
+                Ok(value) => Outcome::Returned(value),
+                Err(err) => Outcome::Failed(format!("{err:#}")),
+            };
+            outcomes.push((backend.clone(), outcome));
+        }
+        Ok(outcomes)
+    }
+
+    /// Call the message with `args` on all backends, and check that all outcomes agree.
+    ///
+    /// If the workload allows divergence for `args`, see [`Workload::allow_divergence_on`], each
+    /// contract is only compared with itself across the backends it is instantiated on.
+    ///
+    /// Returns the outcome of the first backend, or an error describing the outcome of every
+    /// backend.
+    pub fn check(&mut self, args: &[DynSolValue]) -> anyhow::Result<Outcome> {
+        let outcomes = self.call(args)?;
+        let per_contract = self.workload.allows_divergence(args);
+        let agree = outcomes.iter().all(|(backend, outcome)| {
+            let (_, first) = outcomes
+                .iter()
+                .find(|(first, _)| !per_contract || first.contract == backend.contract)
+                .expect("the backend itself is compared");
+            outcome == first
+        });
+        if agree {
+            Ok(outcomes[0].1.clone())
+        } else {
+            Err(self.divergence(args, &outcomes, None))
+        }
+    }
+
+    /// Call the message with `args` on all backends, and check that all of them return
+    /// `expected`, e.g. as computed by a reference implementation.
+    pub fn check_expected(
+        &mut self,
+        args: &[DynSolValue],
+        expected: &[DynSolValue],
+    ) -> anyhow::Result<()> {
+        let expected = Outcome::Returned(DynSolValue::Tuple(expected.to_vec()));
+        let outcomes = self.call(args)?;
+        if outcomes.iter().all(|(_, outcome)| outcome == &expected) {
+            Ok(())
+        } else {
+            Err(self.divergence(args, &outcomes, Some(&expected)))
+        }
+    }
+
+    fn divergence(
+        &self,
+        args: &[DynSolValue],
+        outcomes: &[(Backend, Outcome)],
+        expected: Option<&Outcome>,
+    ) -> anyhow::Error {
+        let mut message = format!("Backends disagree on {}({args:?})\n", self.workload.message);
+        if let Some(expected) = expected {
+            message.push_str(&format!("  expected: {expected}\n"));
+        }
+        for (backend, outcome) in outcomes {
+            message.push_str(&format!("  {backend}: {outcome}\n"));
+        }
+        anyhow::anyhow!(message)
+    }
+}
+
+/// The selector of an ink! message or constructor without an explicit selector: the first four
+/// bytes of the BLAKE2b-256 hash of its name.
+pub fn ink_selector(label: &str) -> [u8; 4] {
+    let hash = sp_core::hashing::blake2_256(label.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// SCALE encode a value, using the equivalent Rust type of each Solidity type.
+pub fn scale_encode(value: &DynSolValue) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    scale_encode_to(value, &mut out)?;
+    Ok(out)
+}
+
+fn scale_encode_to(value: &DynSolValue, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    match value {
+        DynSolValue::Bool(b) => b.encode_to(out),
+        DynSolValue::Int(int, bits) => {
+            out.extend_from_slice(&int.into_raw().to_le_bytes::<32>()[..bits / 8])
+        }
+        DynSolValue::Uint(uint, bits) => {
+            out.extend_from_slice(&uint.to_le_bytes::<32>()[..bits / 8])
+        }
+        DynSolValue::FixedBytes(word, size) => out.extend_from_slice(&word[..*size]),
+        DynSolValue::Bytes(bytes) => bytes.encode_to(out),
+        DynSolValue::String(string) => string.encode_to(out),
+        DynSolValue::Array(values) => {
+            Compact(values.len() as u32).encode_to(out);
+            for value in values {
+                scale_encode_to(value, out)?;
+            }
+        }
+        DynSolValue::FixedArray(values) | DynSolValue::Tuple(values) => {
+            for value in values {
+                scale_encode_to(value, out)?;
+            }
+        }
+        value => anyhow::bail!("SCALE encoding of {value:?} is not supported"),
+    }
+    Ok(())
+}
+
+/// SCALE decode the outputs of a message, returned as a tuple.
+pub fn scale_decode_outputs(outputs: &[DynSolType], data: &[u8]) -> anyhow::Result<DynSolValue> {
+    let input = &mut &data[..];
+    let value = scale_decode(&DynSolType::Tuple(outputs.to_vec()), input)?;
+    if !input.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} trailing bytes after decoding outputs",
+            input.len()
+        ));
+    }
+    Ok(value)
+}
+
+/// SCALE decode a value of the given type, using the equivalent Rust type of each Solidity type.
+pub fn scale_decode(ty: &DynSolType, input: &mut &[u8]) -> anyhow::Result<DynSolValue> {
+    let value = match ty {
+        DynSolType::Bool => DynSolValue::Bool(bool::decode(input)?),
+        DynSolType::Int(bits) => {
+            let bytes = take(input, bits / 8, ty)?;
+            // sign extend to 256 bits
+            let fill = if bytes.last().is_some_and(|b| b & 0x80 != 0) {
+                0xff
+            } else {
+                0
+            };
+            let mut le = [fill; 32];
+            le[..bytes.len()].copy_from_slice(&bytes);
+            DynSolValue::Int(I256::from_raw(U256::from_le_bytes(le)), *bits)
+        }
+        DynSolType::Uint(bits) => {
+            let bytes = take(input, bits / 8, ty)?;
+            let mut le = [0u8; 32];
+            le[..bytes.len()].copy_from_slice(&bytes);
+            DynSolValue::Uint(U256::from_le_bytes(le), *bits)
+        }
+        DynSolType::FixedBytes(size) => {
+            let bytes = take(input, *size, ty)?;
+            let mut word = alloy_primitives::B256::ZERO;
+            word[..*size].copy_from_slice(&bytes);
+            DynSolValue::FixedBytes(word, *size)
+        }
+        DynSolType::Bytes => DynSolValue::Bytes(Vec::<u8>::decode(input)?),
+        DynSolType::String => DynSolValue::String(String::decode(input)?),
+        DynSolType::Array(ty) => {
+            let len = Compact::<u32>::decode(input)?.0;
+            let values = (0..len)
+                .map(|_| scale_decode(ty, input))
+                .collect::<anyhow::Result<_>>()?;
+            DynSolValue::Array(values)
+        }
+        DynSolType::FixedArray(ty, len) => {
+            let values = (0..*len)
+                .map(|_| scale_decode(ty, input))
+                .collect::<anyhow::Result<_>>()?;
+            DynSolValue::FixedArray(values)
+        }
+        DynSolType::Tuple(types) => {
+            let values = types
+                .iter()
+                .map(|ty| scale_decode(ty, input))
+                .collect::<anyhow::Result<_>>()?;
+            DynSolValue::Tuple(values)
+        }
+        ty => return Err(anyhow::anyhow!("SCALE decoding of {ty} is not supported")),
+    };
+    Ok(value)
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize, ty: &DynSolType) -> anyhow::Result<&'a [u8]> {
+    if input.len() < len {
+        return Err(anyhow::anyhow!("Not enough data to decode {ty}"));
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
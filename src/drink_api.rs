@@ -1,16 +1,50 @@
 use super::drink::{
-    frame_support::traits::fungible::Inspect,
-    pallet_balances, pallet_contracts,
+    frame_support::{
+        sp_runtime::{traits::UniqueSaturatedInto, DispatchError, TransactionOutcome},
+        storage::{storage_prefix, unhashed, with_transaction},
+        traits::{fungible::Inspect, Get, PalletInfoAccess},
+        StorageHasher, Twox64Concat,
+    },
+    frame_system, pallet_balances,
+    pallet_contracts::{self, CollectEvents, DebugInfo, Determinism, StorageDeposit},
     runtime::{AccountIdFor, Runtime as RuntimeT},
     BalanceOf, Sandbox, Weight, DEFAULT_GAS_LIMIT,
 };
+
+use crate::{
+    accounts::Accounts,
+    cost::CallCost,
+    logging,
+    storage::{InkStorageLayout, StorageDump, StorageField},
+    strace::{self, HostFnClock, HostFnTrace, HostFnWeights},
+};
+use frame_metadata::{v14::StorageEntryType, RuntimeMetadata};
+use parity_scale_codec::Encode;
+use scale_value::{Composite, ValueDef};
+#[cfg(feature = "wasm")]
+use sp_io::default_child_storage;
+#[cfg(feature = "riscv")]
+use sp_io_riscv::default_child_storage;
+use std::time::Instant;
 use subxt_signer::sr25519::{dev, Keypair};
+use tracing_subscriber::EnvFilter;
+
+pub type CodeHashFor<R> = <R as frame_system::Config>::Hash;
 
 pub type ContractsBalanceOf<R> =
     <<R as pallet_contracts::Config>::Currency as Inspect<AccountIdFor<R>>>::Balance;
 
 pub struct DrinkApi<Runtime: RuntimeT> {
     sandbox: Sandbox<Runtime>,
+    /// Host function weights from the contracts `Schedule`, present when host function tracing
+    /// is enabled.
+    host_fn_weights: Option<HostFnWeights>,
+    /// Whether contracts may write to the debug buffer during calls.
+    debug_messages: bool,
+    /// The debug messages of all calls and instantiations, present when the log is enabled.
+    debug_log: Option<Vec<String>>,
+    /// The filter directives of the logs to capture during calls, if log capture is enabled.
+    log_capture: Option<String>,
 }
 
 impl<Runtime> DrinkApi<Runtime>
@@ -20,15 +54,99 @@ where
     BalanceOf<Runtime>: From<u128>,
 {
     pub fn new() -> Self {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::WARN)
-            .with_test_writer()
-            .try_init()
-            .ok();
-
         let mut sandbox = Sandbox::new().expect("Failed to initialize Drink! sandbox");
         Self::fund_accounts(&mut sandbox);
-        DrinkApi { sandbox }
+        DrinkApi {
+            sandbox,
+            host_fn_weights: None,
+            debug_messages: true,
+            debug_log: None,
+            log_capture: None,
+        }
+    }
+
+    /// Execute the given closure within the externalities of the sandbox.
+    pub fn execute_with<T>(&mut self, execute: impl FnOnce() -> T) -> T {
+        self.sandbox.execute_with(execute)
+    }
+
+    /// The free balance of an account.
+    pub fn free_balance(&mut self, account: &AccountIdFor<Runtime>) -> BalanceOf<Runtime> {
+        self.execute_with(|| pallet_balances::Pallet::<Runtime>::free_balance(account))
+    }
+
+    fn balances(
+        &mut self,
+        caller: &AccountIdFor<Runtime>,
+        contract: &AccountIdFor<Runtime>,
+    ) -> (u128, u128) {
+        (
+            self.free_balance(caller).unique_saturated_into(),
+            self.free_balance(contract).unique_saturated_into(),
+        )
+    }
+
+    /// Enable or disable host function tracing for subsequent calls.
+    ///
+    /// When enabled, [`CallOutcome::host_fns`] contains a breakdown of the host functions invoked
+    /// by each call. Tracing adds overhead to every host function call, so it should not be
+    /// enabled while benchmarking.
+    pub fn set_host_fn_tracing(&mut self, enabled: bool) -> anyhow::Result<()>
+    where
+        pallet_contracts::Schedule<Runtime>: serde::Serialize,
+    {
+        self.host_fn_weights = if enabled {
+            let schedule = self.execute_with(<Runtime as pallet_contracts::Config>::Schedule::get);
+            Some(strace::host_fn_weights(&schedule)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    /// Enable or disable the debug buffer for subsequent calls.
+    ///
+    /// When disabled, `debug_println!` and the like are no-ops and [`CallOutcome::debug_message`]
+    /// is empty. Host function tracing relies on the debug buffer, so enabling it overrides this.
+    pub fn set_debug_messages(&mut self, enabled: bool) {
+        self.debug_messages = enabled;
+    }
+
+    /// Enable or disable collecting the debug messages of subsequent calls and instantiations
+    /// into the [`Self::debug_log`].
+    pub fn set_debug_log(&mut self, enabled: bool) {
+        self.debug_log = enabled.then(Vec::new);
+    }
+
+    /// The lines of all debug messages collected since the log was enabled.
+    pub fn debug_log(&self) -> &[String] {
+        self.debug_log.as_deref().unwrap_or_default()
+    }
+
+    /// Assert that a line containing `expected` was printed since the log was enabled.
+    #[track_caller]
+    pub fn assert_printed(&self, expected: &str) {
+        assert_printed(self.debug_log().iter().map(String::as_str), expected)
+    }
+
+    /// Capture the logs matching the `filter` directives during subsequent calls, or disable log
+    /// capture if `None`.
+    ///
+    /// Captured logs are returned in [`CallOutcome::logs`], and included in the error if a call
+    /// fails. See [`logging::capture`].
+    pub fn set_log_capture(&mut self, filter: Option<&str>) {
+        self.log_capture = filter.map(ToString::to_string);
+    }
+
+    /// Forward a debug message to `tracing` and the debug log.
+    fn record_debug_message(&mut self, debug_message: &str) {
+        if debug_message.is_empty() {
+            return;
+        }
+        tracing::debug!("debug message: {}", debug_message);
+        if let Some(log) = self.debug_log.as_mut() {
+            log.extend(debug_message.lines().map(ToString::to_string));
+        }
     }
 
     pub fn fund_accounts(sandbox: &mut Sandbox<Runtime>) {
@@ -53,10 +171,87 @@ where
         }
     }
 
+    /// Fund the contracts accounts of `accounts` with their configured balances.
+    pub fn fund(&mut self, accounts: &Accounts) -> anyhow::Result<()> {
+        for (account, balance) in accounts.funded_account_ids::<AccountIdFor<Runtime>>() {
+            self.sandbox
+                .mint_into(account, balance.into())
+                .map_err(|err| anyhow::anyhow!("Failed to mint {balance} tokens: {err:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// Upload `code` without instantiating it, returning its code hash.
+    pub fn upload_code(
+        &mut self,
+        code: Vec<u8>,
+        caller: Keypair,
+    ) -> anyhow::Result<CodeHashFor<Runtime>> {
+        self.sandbox
+            .upload_contract(
+                code,
+                keypair_to_account(&caller),
+                None,
+                Determinism::Enforced,
+            )
+            .map(|result| result.code_hash)
+            .map_err(|e| anyhow::anyhow!("Failed to upload code: {:?}", e))
+    }
+
+    /// Instantiate a contract from code previously uploaded with [`Self::upload_code`].
+    pub fn instantiate(
+        &mut self,
+        instantiate_args: InstantiateArgs<Runtime>,
+    ) -> anyhow::Result<AccountIdFor<Runtime>> {
+        self.instantiate_with_outcome(instantiate_args)
+            .map(|outcome| outcome.account_id)
+    }
+
+    /// Instantiate a contract from uploaded code, returning its account along with the storage
+    /// deposit and balance changes caused by the instantiation.
+    pub fn instantiate_with_outcome(
+        &mut self,
+        instantiate_args: InstantiateArgs<Runtime>,
+    ) -> anyhow::Result<InstantiateOutcome<AccountIdFor<Runtime>>> {
+        let InstantiateArgs {
+            code_hash,
+            value,
+            data,
+            salt,
+            caller,
+            storage_deposit_limit,
+        } = instantiate_args;
+        let caller_before = self.free_balance(&caller).unique_saturated_into();
+        let result = self.sandbox.instantiate_contract(
+            code_hash.encode(),
+            value,
+            data,
+            salt,
+            caller.clone(),
+            DEFAULT_GAS_LIMIT,
+            storage_deposit_limit,
+        );
+        self.record_debug_message(&String::from_utf8_lossy(&result.debug_message));
+        let storage_deposit = deposit_delta(result.storage_deposit);
+        let account_id = result.result.map(|r| r.account_id);
+        self.instantiated(&caller, caller_before, storage_deposit, account_id)
+    }
+
+    /// Upload the code of `create_args` and instantiate a contract from it.
     pub fn instantiate_with_code(
         &mut self,
         create_args: CreateArgs<Runtime>,
     ) -> anyhow::Result<AccountIdFor<Runtime>> {
+        self.instantiate_with_code_and_outcome(create_args)
+            .map(|outcome| outcome.account_id)
+    }
+
+    /// Upload code and instantiate a contract from it, returning its account along with the
+    /// storage deposit, for both the code and the contract, and balance changes.
+    pub fn instantiate_with_code_and_outcome(
+        &mut self,
+        create_args: CreateArgs<Runtime>,
+    ) -> anyhow::Result<InstantiateOutcome<AccountIdFor<Runtime>>> {
         let CreateArgs {
             code,
             value,
@@ -65,28 +260,154 @@ where
             caller,
             storage_deposit_limit,
         } = create_args;
+        let caller_before = self.free_balance(&caller).unique_saturated_into();
         let result = self.sandbox.deploy_contract(
             code,
             value,
             data,
             salt,
-            caller,
+            caller.clone(),
             DEFAULT_GAS_LIMIT,
             storage_deposit_limit,
         );
-        if !result.debug_message.is_empty() {
-            tracing::debug!(
-                "debug message {}",
-                String::from_utf8_lossy(&result.debug_message)
-            )
-        }
-        result
-            .result
-            .map(|r| r.account_id)
-            .map_err(|e| anyhow::anyhow!("Failed to instantiate contract: {:?}", e))
+        self.record_debug_message(&String::from_utf8_lossy(&result.debug_message));
+        let storage_deposit = deposit_delta(result.storage_deposit);
+        let account_id = result.result.map(|r| r.account_id);
+        self.instantiated(&caller, caller_before, storage_deposit, account_id)
+    }
+
+    fn instantiated(
+        &mut self,
+        caller: &AccountIdFor<Runtime>,
+        caller_before: u128,
+        storage_deposit: i128,
+        account_id: Result<AccountIdFor<Runtime>, DispatchError>,
+    ) -> anyhow::Result<InstantiateOutcome<AccountIdFor<Runtime>>> {
+        let account_id =
+            account_id.map_err(|e| anyhow::anyhow!("Failed to instantiate contract: {:?}", e))?;
+        let (caller_after, contract_balance) = self.balances(caller, &account_id);
+        Ok(InstantiateOutcome {
+            account_id,
+            cost: CallCost {
+                storage_deposit,
+                caller_balance: CallCost::delta(caller_before, caller_after),
+                contract_balance: CallCost::delta(0, contract_balance),
+            },
+        })
+    }
+
+    /// The address a contract instantiated by `deployer` from `code_hash` with the given input
+    /// data and salt is deployed at.
+    pub fn contract_address(
+        &mut self,
+        deployer: &AccountIdFor<Runtime>,
+        code_hash: &CodeHashFor<Runtime>,
+        data: &[u8],
+        salt: &[u8],
+    ) -> AccountIdFor<Runtime> {
+        self.execute_with(|| {
+            pallet_contracts::Pallet::<Runtime>::contract_address(deployer, code_hash, data, salt)
+        })
+    }
+
+    /// Read the storage item of `contract` under the unhashed `key`.
+    pub fn read_storage(
+        &mut self,
+        contract: AccountIdFor<Runtime>,
+        key: Vec<u8>,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        self.execute_with(|| pallet_contracts::Pallet::<Runtime>::get_storage(contract, key))
+            .map_err(|e| anyhow::anyhow!("Failed to read contract storage: {:?}", e))
+    }
+
+    /// Read all storage items of `contract`.
+    pub fn dump_storage(
+        &mut self,
+        contract: &AccountIdFor<Runtime>,
+    ) -> anyhow::Result<StorageDump> {
+        let trie_id = self.trie_id(contract)?;
+        self.execute_with(|| {
+            let mut dump = StorageDump::default();
+            let mut key = Vec::new();
+            while let Some(next) = default_child_storage::next_key(&trie_id, &key) {
+                if let Some(value) = default_child_storage::get(&trie_id, &next) {
+                    dump.entries.insert(next.clone(), value);
+                }
+                key = next;
+            }
+            Ok(dump)
+        })
+    }
+
+    /// The id of the child trie holding the storage of `contract`.
+    ///
+    /// `ContractInfo` is private to `pallet-contracts`, so the `ContractInfoOf` entry is decoded
+    /// with its type from the runtime metadata instead of assuming its layout.
+    fn trie_id(&mut self, contract: &AccountIdFor<Runtime>) -> anyhow::Result<Vec<u8>> {
+        let RuntimeMetadata::V14(metadata) = Runtime::get_metadata().1 else {
+            anyhow::bail!("Unsupported runtime metadata version");
+        };
+        let pallet_name = <pallet_contracts::Pallet<Runtime> as PalletInfoAccess>::name();
+        let storage = metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.name == pallet_name)
+            .and_then(|pallet| pallet.storage.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("No storage of {pallet_name} in metadata"))?;
+        let info_ty = storage
+            .entries
+            .iter()
+            .find_map(|entry| match &entry.ty {
+                StorageEntryType::Map { value, .. } if entry.name == "ContractInfoOf" => {
+                    Some(value.id)
+                }
+                _ => None,
+            })
+            .ok_or_else(|| anyhow::anyhow!("No ContractInfoOf storage in metadata"))?;
+
+        let mut info_key = storage_prefix(storage.prefix.as_bytes(), b"ContractInfoOf").to_vec();
+        info_key.extend(Twox64Concat::hash(&contract.encode()));
+        let info = self
+            .execute_with(|| unhashed::get_raw(&info_key))
+            .ok_or_else(|| anyhow::anyhow!("No contract at {:?}", contract))?;
+        let info = scale_value::scale::decode_as_type(&mut &info[..], info_ty, &metadata.types)
+            .map_err(|err| anyhow::anyhow!("Error decoding contract info: {err:?}"))?;
+
+        let trie_id = match info.value {
+            ValueDef::Composite(Composite::Named(fields)) => fields
+                .into_iter()
+                .find_map(|(name, value)| (name == "trie_id").then_some(value)),
+            _ => None,
+        };
+        trie_id
+            .as_ref()
+            .and_then(value_bytes)
+            .ok_or_else(|| anyhow::anyhow!("No trie id in contract info of {:?}", contract))
+    }
+
+    /// Read and decode the root storage struct of an ink! contract.
+    pub fn read_ink_root(
+        &mut self,
+        contract: AccountIdFor<Runtime>,
+        layout: &InkStorageLayout,
+    ) -> anyhow::Result<Vec<(String, StorageField)>> {
+        let value = self
+            .read_storage(contract, layout.root_key())?
+            .ok_or_else(|| anyhow::anyhow!("No root storage item"))?;
+        layout.decode_root(&value)
     }
 
     pub fn call(&mut self, call_args: CallArgs<Runtime>) -> anyhow::Result<Vec<u8>> {
+        self.call_with_outcome(call_args)
+            .map(|outcome| outcome.data)
+    }
+
+    /// Call a contract, returning the data along with any additional information collected
+    /// during the call.
+    pub fn call_with_outcome(
+        &mut self,
+        call_args: CallArgs<Runtime>,
+    ) -> anyhow::Result<CallOutcome> {
         let CallArgs {
             contract_account,
             caller,
@@ -94,34 +415,188 @@ where
             value,
             gas_limit,
             storage_deposit_limit,
+            determinism,
         } = call_args;
         let gas_limit = gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
-        let result = self.sandbox.call_contract(
-            contract_account,
-            value,
-            exec_input,
-            caller,
-            gas_limit,
-            storage_deposit_limit,
-            pallet_contracts::Determinism::Enforced,
-        );
-        if !result.debug_message.is_empty() {
-            tracing::debug!(
-                "debug message: {}",
-                String::from_utf8_lossy(&result.debug_message)
-            )
-        }
+        let (caller_before, contract_before) = self.balances(&caller, &contract_account);
+        let accounts = (caller.clone(), contract_account.clone());
+        let debug = if self.debug_messages || self.host_fn_weights.is_some() {
+            DebugInfo::UnsafeDebug
+        } else {
+            DebugInfo::Skip
+        };
+        let sandbox = &mut self.sandbox;
+        let call_contract = || {
+            sandbox.execute_with(|| {
+                pallet_contracts::Pallet::<Runtime>::bare_call(
+                    caller,
+                    contract_account,
+                    value,
+                    gas_limit,
+                    storage_deposit_limit,
+                    exec_input,
+                    debug,
+                    CollectEvents::UnsafeCollect,
+                    determinism,
+                )
+            })
+        };
+        let capture = self
+            .log_capture
+            .as_deref()
+            .map(EnvFilter::try_new)
+            .transpose()?;
+        let clock = self.host_fn_weights.is_some().then(HostFnClock::default);
+        let start = Instant::now();
+        let (result, logs) = logging::scoped(capture, clock.clone(), call_contract);
+        let end = Instant::now();
+        // include any captured logs in errors, to explain the failure
+        let with_logs = |message: String| match &logs {
+            Some(logs) => anyhow::anyhow!("{message}\n\nCaptured logs:\n{logs}"),
+            None => anyhow::anyhow!(message),
+        };
+        let debug_message = String::from_utf8_lossy(&result.debug_message);
+        let mut host_fns = None;
+        let debug_message = match (&clock, &self.host_fn_weights) {
+            (Some(clock), Some(weights)) => {
+                let mut trace = HostFnTrace {
+                    elapsed: end - start,
+                    ..Default::default()
+                };
+                let remaining = trace.parse(&debug_message, weights, &clock.durations(end));
+                host_fns = Some(trace);
+                remaining
+            }
+            _ => debug_message.into_owned(),
+        };
+        self.record_debug_message(&debug_message);
+        let storage_deposit = deposit_delta(result.storage_deposit);
         match result.result {
             Ok(result) => {
                 if result.did_revert() {
                     tracing::error!("contract reverted with {:?}", result);
-                    return Err(anyhow::anyhow!("Contract execution reverted"));
+                    return Err(with_logs("Contract execution reverted".to_string()));
                 }
-                Ok(result.data)
+                let (caller_after, contract_after) = self.balances(&accounts.0, &accounts.1);
+                Ok(CallOutcome {
+                    data: result.data,
+                    host_fns,
+                    debug_message,
+                    logs,
+                    cost: CallCost {
+                        storage_deposit,
+                        caller_balance: CallCost::delta(caller_before, caller_after),
+                        contract_balance: CallCost::delta(contract_before, contract_after),
+                    },
+                })
             }
-            Err(e) => Err(anyhow::anyhow!("Failed to call contract: {:?}", e)),
+            Err(e) => Err(with_logs(format!("Failed to call contract: {:?}", e))),
         }
     }
+
+    /// Dry-run a call, returning the weight and storage deposit it requires, like the
+    /// `ContractsApi_call` runtime API. All state changes are rolled back.
+    pub fn estimate(&mut self, call_args: CallArgs<Runtime>) -> anyhow::Result<Estimate> {
+        let CallArgs {
+            contract_account,
+            caller,
+            exec_input,
+            value,
+            gas_limit,
+            storage_deposit_limit,
+            determinism,
+        } = call_args;
+        let gas_limit = gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
+        let result = self
+            .execute_with(|| {
+                with_transaction(|| {
+                    let result = pallet_contracts::Pallet::<Runtime>::bare_call(
+                        caller,
+                        contract_account,
+                        value,
+                        gas_limit,
+                        storage_deposit_limit,
+                        exec_input,
+                        DebugInfo::Skip,
+                        CollectEvents::Skip,
+                        determinism,
+                    );
+                    TransactionOutcome::Rollback(Ok::<_, DispatchError>(result))
+                })
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to dry-run call: {:?}", e))?;
+
+        match result.result {
+            Ok(exec) if exec.did_revert() => Err(anyhow::anyhow!("Contract execution reverted")),
+            Ok(_) => Ok(Estimate {
+                gas_required: result.gas_required,
+                storage_deposit: deposit_delta(result.storage_deposit),
+            }),
+            Err(e) => Err(anyhow::anyhow!("Failed to dry-run call: {:?}", e)),
+        }
+    }
+
+    /// Estimate a call, then dry-run it again with the estimated limits to validate them.
+    pub fn estimate_validated(&mut self, call_args: CallArgs<Runtime>) -> anyhow::Result<Estimate>
+    where
+        ContractsBalanceOf<Runtime>: From<u128>,
+    {
+        let estimate = self.estimate(call_args.clone())?;
+        self.estimate(call_args.with_estimate(&estimate))
+            .map_err(|e| anyhow::anyhow!("Call fails with the estimated limits: {e}"))?;
+        Ok(estimate)
+    }
+}
+
+/// The resources a call requires, see [`DrinkApi::estimate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    /// The weight required to execute the call, to be used as its gas limit.
+    pub gas_required: Weight,
+    /// The storage deposit charged by the call, or refunded if negative.
+    pub storage_deposit: i128,
+}
+
+/// The outcome of a successful instantiation.
+#[derive(Debug, Clone)]
+pub struct InstantiateOutcome<AccountId> {
+    /// The account of the instantiated contract.
+    pub account_id: AccountId,
+    /// The storage deposit and balance changes caused by the instantiation.
+    pub cost: CallCost,
+}
+
+/// The outcome of a successful contract call.
+#[derive(Debug, Clone)]
+pub struct CallOutcome {
+    /// The data returned by the contract.
+    pub data: Vec<u8>,
+    /// The host functions invoked by the call, if host function tracing is enabled.
+    pub host_fns: Option<HostFnTrace>,
+    /// The debug message printed by the contract, if debug messages are enabled.
+    pub debug_message: String,
+    /// The logs emitted during the call, if log capture is enabled.
+    pub logs: Option<String>,
+    /// The storage deposit and balance changes caused by the call.
+    pub cost: CallCost,
+}
+
+impl CallOutcome {
+    /// Assert that a line containing `expected` was printed during the call.
+    #[track_caller]
+    pub fn assert_printed(&self, expected: &str) {
+        assert_printed(self.debug_message.lines(), expected)
+    }
+}
+
+#[track_caller]
+fn assert_printed<'a>(lines: impl Iterator<Item = &'a str>, expected: &str) {
+    let lines = lines.collect::<Vec<_>>();
+    assert!(
+        lines.iter().any(|line| line.contains(expected)),
+        "Expected a debug message containing {expected:?}, printed:\n{}",
+        lines.join("\n")
+    );
 }
 
 #[derive(Clone)]
@@ -156,6 +631,82 @@ impl<Runtime: RuntimeT + pallet_contracts::Config> CreateArgs<Runtime> {
         self.data = data;
         self
     }
+
+    /// Set the salt, to instantiate multiple contracts with the same code and input data.
+    pub fn with_salt(mut self, salt: Vec<u8>) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    /// Set the value transferred to the contract on instantiation.
+    pub fn with_value(mut self, value: ContractsBalanceOf<Runtime>) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn with_storage_deposit_limit(
+        mut self,
+        storage_deposit_limit: Option<ContractsBalanceOf<Runtime>>,
+    ) -> Self {
+        self.storage_deposit_limit = storage_deposit_limit;
+        self
+    }
+}
+
+/// The arguments to instantiate a contract from code previously uploaded with
+/// [`DrinkApi::upload_code`], see [`DrinkApi::instantiate`].
+#[derive(Clone)]
+pub struct InstantiateArgs<Runtime: RuntimeT + pallet_contracts::Config> {
+    pub code_hash: CodeHashFor<Runtime>,
+    pub value: ContractsBalanceOf<Runtime>,
+    pub data: Vec<u8>,
+    pub salt: Vec<u8>,
+    pub caller: AccountIdFor<Runtime>,
+    pub storage_deposit_limit: Option<ContractsBalanceOf<Runtime>>,
+}
+
+impl<Runtime: RuntimeT + pallet_contracts::Config> InstantiateArgs<Runtime>
+where
+    AccountIdFor<Runtime>: From<[u8; 32]>,
+    ContractsBalanceOf<Runtime>: From<u128>,
+{
+    pub fn new(code_hash: CodeHashFor<Runtime>, caller: Keypair) -> Self {
+        Self {
+            code_hash,
+            value: ContractsBalanceOf::<Runtime>::from(0u128),
+            data: Vec::new(),
+            salt: Vec::new(),
+            caller: keypair_to_account(&caller),
+            storage_deposit_limit: None,
+        }
+    }
+}
+
+impl<Runtime: RuntimeT + pallet_contracts::Config> InstantiateArgs<Runtime> {
+    pub fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Set the salt, to instantiate multiple contracts with the same code and input data.
+    pub fn with_salt(mut self, salt: Vec<u8>) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    /// Set the value transferred to the contract on instantiation.
+    pub fn with_value(mut self, value: ContractsBalanceOf<Runtime>) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn with_storage_deposit_limit(
+        mut self,
+        storage_deposit_limit: Option<ContractsBalanceOf<Runtime>>,
+    ) -> Self {
+        self.storage_deposit_limit = storage_deposit_limit;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -166,6 +717,7 @@ pub struct CallArgs<Runtime: RuntimeT + pallet_contracts::Config> {
     pub value: ContractsBalanceOf<Runtime>,
     pub gas_limit: Option<Weight>,
     pub storage_deposit_limit: Option<ContractsBalanceOf<Runtime>>,
+    determinism: Determinism,
 }
 
 impl<Runtime: RuntimeT + pallet_contracts::Config> CallArgs<Runtime>
@@ -184,6 +736,7 @@ where
             value: Default::default(),
             storage_deposit_limit: None,
             gas_limit: None,
+            determinism: Determinism::Enforced,
         }
     }
 
@@ -208,6 +761,47 @@ where
     pub fn with_max_gas_limit(self) -> Self {
         self.with_gas_limit(Weight::from_parts(u64::MAX, u64::MAX))
     }
+
+    /// Submit the call with the gas limit and storage deposit limit of `estimate`.
+    pub fn with_estimate(mut self, estimate: &Estimate) -> Self
+    where
+        ContractsBalanceOf<Runtime>: From<u128>,
+    {
+        let deposit_limit = estimate.storage_deposit.max(0) as u128;
+        self.gas_limit = Some(estimate.gas_required);
+        self.storage_deposit_limit = Some(deposit_limit.into());
+        self
+    }
+
+    /// Allow the call to execute non-deterministic code, e.g. floating point instructions.
+    pub fn with_determinism(mut self, determinism: Determinism) -> Self {
+        self.determinism = determinism;
+        self
+    }
+}
+
+/// The signed storage deposit, positive if charged and negative if refunded.
+fn deposit_delta<Balance: UniqueSaturatedInto<u128>>(deposit: StorageDeposit<Balance>) -> i128 {
+    match deposit {
+        StorageDeposit::Charge(amount) => CallCost::delta(0, amount.unique_saturated_into()),
+        StorageDeposit::Refund(amount) => CallCost::delta(amount.unique_saturated_into(), 0),
+    }
+}
+
+/// The bytes of a decoded byte sequence, e.g. a `Vec<u8>` or a `BoundedVec<u8, _>`.
+fn value_bytes(value: &scale_value::Value<u32>) -> Option<Vec<u8>> {
+    match &value.value {
+        ValueDef::Composite(composite) => {
+            composite.values().try_fold(Vec::new(), |mut bytes, value| {
+                bytes.extend(value_bytes(value)?);
+                Some(bytes)
+            })
+        }
+        _ => value
+            .as_u128()
+            .and_then(|byte| u8::try_from(byte).ok())
+            .map(|byte| vec![byte]),
+    }
 }
 
 fn keypair_to_account<AccountId: From<[u8; 32]>>(keypair: &Keypair) -> AccountId {
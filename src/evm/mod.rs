@@ -1,9 +1,14 @@
+pub mod precompiles;
 mod runtime;
+pub mod tracer;
+
+use std::collections::BTreeMap;
 
 use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
 use alloy_json_abi::JsonAbi;
-use fp_evm::{CreateInfo, ExitReason};
-use frame_support::sp_runtime;
+use fp_evm::{CallInfo, CreateInfo, ExitReason};
+use frame_support::sp_runtime::{self, TransactionOutcome};
+use frame_support::storage::with_transaction;
 use frame_support::traits::fungible::Mutate;
 use frame_system::GenesisConfig;
 use pallet_evm::Runner;
@@ -11,7 +16,9 @@ use sp_core::{H160, H256, U256};
 use sp_io::TestExternalities;
 use sp_runtime::BuildStorage;
 
-pub use runtime::EvmRuntime;
+use crate::{accounts::Accounts, cost::CallCost, storage::StorageDump};
+pub use runtime::{EvmConfig, EvmRuntime};
+use tracer::{EvmTrace, TracerConfig};
 
 pub type AccountIdFor<R> = <R as frame_system::Config>::AccountId;
 pub type BalanceOf<R> = <R as pallet_balances::Config>::Balance;
@@ -30,30 +37,51 @@ pub const ACCOUNTS: [H160; 8] = [
 pub struct EvmContract {
     address: H160,
     abi: JsonAbi,
+    max_fee_per_gas: U256,
     pub sandbox: EvmSandbox<EvmRuntime>,
 }
 
 impl EvmContract {
     pub fn init(contract: &str) -> Self {
+        Self::init_with_config(contract, EvmConfig::default())
+    }
+
+    /// Build and deploy the contract in a sandbox with the given runtime parameters.
+    ///
+    /// Calls are submitted with `max_fee_per_gas` equal to the configured minimum gas price.
+    pub fn init_with_config(contract: &str, config: EvmConfig) -> Self {
         let result =
             crate::solc::build_contract(&format!("contracts/solidity/{}.sol", contract)).unwrap();
-        let mut sandbox = EvmSandbox::<EvmRuntime>::new();
+        let max_fee_per_gas = config.min_gas_price;
+        let mut sandbox = EvmSandbox::<EvmRuntime>::new().with_config(config);
 
         let create_args = CreateArgs {
             source: ACCOUNTS[0],
             init: result.code,
             gas_limit: 1_000_000_000,
-            max_fee_per_gas: U256::from(1_000_000_000),
+            max_fee_per_gas,
             ..Default::default()
         };
         let address = sandbox.create(create_args).unwrap();
         EvmContract {
             address,
             abi: result.abi,
+            max_fee_per_gas,
             sandbox,
         }
     }
 
+    /// The address the contract is deployed at.
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+
+    /// Set how transaction fees are accounted for by the sandbox of the contract.
+    pub fn with_fee_mode(mut self, fee_mode: FeeMode) -> Self {
+        self.sandbox = self.sandbox.with_fee_mode(fee_mode);
+        self
+    }
+
     pub fn call_args(&self, func: &str, args: &[DynSolValue]) -> CallArgs {
         let func = &self.abi.function(func).unwrap()[0];
         let data = func.abi_encode_input(args).unwrap();
@@ -63,14 +91,30 @@ impl EvmContract {
             target: self.address,
             input: data,
             gas_limit: 1_000_000_000,
-            max_fee_per_gas: U256::from(1_000_000_000),
+            max_fee_per_gas: self.max_fee_per_gas,
             ..Default::default()
         }
     }
 }
 
+/// How transaction fees are accounted for by an [`EvmSandbox`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FeeMode {
+    /// Fees are charged from the caller, as on chain.
+    #[default]
+    Charge,
+    /// No fees are charged. Only supported by the [`EvmRuntime`].
+    Zero,
+    /// Fees are charged, and the caller is topped up to its prior balance after each
+    /// transaction, so that repeated transactions never fail with `BalanceLow`.
+    TopUp,
+}
+
 pub struct EvmSandbox<R = EvmRuntime> {
     externalities: TestExternalities,
+    fee_mode: FeeMode,
+    /// The next nonce of each source, if nonce tracking is enabled.
+    nonces: Option<BTreeMap<H160, U256>>,
     phantom: std::marker::PhantomData<R>,
 }
 
@@ -97,10 +141,73 @@ where
 
         Self {
             externalities: TestExternalities::new(storage),
+            fee_mode: FeeMode::default(),
+            nonces: Some(BTreeMap::new()),
             phantom: Default::default(),
         }
     }
 
+    /// Enable or disable nonce tracking.
+    ///
+    /// When enabled, transactions without an explicit nonce are submitted with the next nonce of
+    /// their source as tracked by the sandbox, rather than leaving it to the runner.
+    pub fn with_nonce_tracking(mut self, enabled: bool) -> Self {
+        self.nonces = enabled.then(BTreeMap::new);
+        self
+    }
+
+    /// The nonce of the next transaction from `address`.
+    pub fn nonce(&mut self, address: H160) -> U256 {
+        self.execute_with(|| pallet_evm::Pallet::<R>::account_basic(&address).0.nonce)
+    }
+
+    /// Resolve the nonce to submit a transaction from `source` with.
+    fn next_nonce(&mut self, source: H160, nonce: Option<U256>) -> Option<U256> {
+        if nonce.is_some() || self.nonces.is_none() {
+            return nonce;
+        }
+        let tracked = self
+            .nonces
+            .as_ref()
+            .and_then(|nonces| nonces.get(&source).copied());
+        Some(tracked.unwrap_or_else(|| self.nonce(source)))
+    }
+
+    /// Track the nonce of `source` after a transaction.
+    ///
+    /// The runner increments the nonce whenever it executed the transaction, even if it reverted,
+    /// so the nonce is read back rather than derived from the result.
+    fn sync_nonce(&mut self, source: H160) {
+        if self.nonces.is_some() {
+            let nonce = self.nonce(source);
+            if let Some(nonces) = self.nonces.as_mut() {
+                nonces.insert(source, nonce);
+            }
+        }
+    }
+
+    /// Execute a transaction from `source`, applying the fee mode and nonce tracking.
+    fn transact<T>(
+        &mut self,
+        source: H160,
+        nonce: Option<U256>,
+        execute: impl FnOnce(Option<U256>) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let nonce = self.next_nonce(source, nonce);
+        let balance = (self.fee_mode == FeeMode::TopUp).then(|| self.free_balance(source));
+
+        let result = self.execute_with(|| execute(nonce));
+        self.sync_nonce(source);
+
+        if let Some(balance) = balance {
+            let current = self.free_balance(source);
+            if current < balance {
+                self.mint_into(source, balance - current)?;
+            }
+        }
+        result
+    }
+
     pub fn execute_with<T>(&mut self, execute: impl FnOnce() -> T) -> T {
         self.externalities.execute_with(execute)
     }
@@ -116,7 +223,7 @@ where
             nonce,
             access_list,
         } = create_args;
-        self.execute_with(|| {
+        self.transact(source, nonce, |nonce| {
             let is_transactional = true;
             let validate = true;
             let CreateInfo {
@@ -153,6 +260,68 @@ where
     }
 
     pub fn call(&mut self, call_args: CallArgs) -> anyhow::Result<Vec<u8>> {
+        self.call_with_outcome(call_args)
+            .map(|outcome| outcome.data)
+    }
+
+    /// Call a contract, returning the data along with the gas used and balance changes.
+    pub fn call_with_outcome(&mut self, call_args: CallArgs) -> anyhow::Result<CallOutcome> {
+        let (info, cost) = self.run_call(call_args)?;
+        if let ExitReason::Succeed(_) = info.exit_reason {
+            Ok(CallOutcome {
+                data: info.value,
+                used_gas: info.used_gas.standard.low_u64(),
+                cost,
+            })
+        } else {
+            Err(anyhow::anyhow!("call failed: {:?}", info.exit_reason))
+        }
+    }
+
+    /// Dry-run a call, returning the gas it used. All state changes are rolled back.
+    ///
+    /// The gas used may be lower than the gas limit the call requires, e.g. because only 63/64 of
+    /// the available gas is passed on to nested calls. Use [`Self::estimate_gas_validated`] to
+    /// find a sufficient gas limit.
+    pub fn estimate_gas(&mut self, call_args: CallArgs) -> anyhow::Result<u64> {
+        let info = self.dry_run(call_args)?;
+        if let ExitReason::Succeed(_) = info.exit_reason {
+            Ok(info.used_gas.standard.low_u64())
+        } else {
+            Err(anyhow::anyhow!("call failed: {:?}", info.exit_reason))
+        }
+    }
+
+    /// Estimate the gas of a call, then dry-run it again with the estimate as gas limit to
+    /// validate it. If the estimate is insufficient, the lowest sufficient gas limit up to
+    /// `call_args.gas_limit` is searched for, like `eth_estimateGas`.
+    pub fn estimate_gas_validated(&mut self, call_args: CallArgs) -> anyhow::Result<u64> {
+        let estimate = self.estimate_gas(call_args.clone())?;
+        let mut succeeds = |gas_limit| {
+            let call_args = CallArgs {
+                gas_limit,
+                ..call_args.clone()
+            };
+            self.dry_run(call_args)
+                .map(|info| matches!(info.exit_reason, ExitReason::Succeed(_)))
+        };
+        if succeeds(estimate)? {
+            return Ok(estimate);
+        }
+
+        let (mut low, mut high) = (estimate, call_args.gas_limit);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if succeeds(mid)? {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(high)
+    }
+
+    fn dry_run(&mut self, call_args: CallArgs) -> anyhow::Result<CallInfo> {
         let CallArgs {
             source,
             target,
@@ -165,6 +334,74 @@ where
             access_list,
         } = call_args;
         self.execute_with(|| {
+            with_transaction(|| {
+                let is_transactional = true;
+                let validate = true;
+                let result = R::Runner::call(
+                    source,
+                    target,
+                    input,
+                    value,
+                    gas_limit,
+                    Some(max_fee_per_gas),
+                    max_priority_fee_per_gas,
+                    nonce,
+                    access_list,
+                    is_transactional,
+                    validate,
+                    None,
+                    None,
+                    R::config(),
+                );
+                TransactionOutcome::Rollback(Ok::<_, sp_runtime::DispatchError>(result))
+            })
+        })
+        .map_err(|err| anyhow::anyhow!("error dry-running call: {:?}", err))?
+        .map_err(|err| {
+            let err: sp_runtime::DispatchError = err.error.into();
+            let ser_err = serde_json::to_string_pretty(&err).unwrap();
+            anyhow::anyhow!("error invoking call: {}", ser_err)
+        })
+    }
+
+    /// Call a contract while recording an opcode level trace of the execution.
+    ///
+    /// Returns an error only if the call could not be executed, e.g. due to insufficient balance.
+    /// Calls which fail during execution are returned as a trace with `failed` set.
+    pub fn call_traced(
+        &mut self,
+        call_args: CallArgs,
+        config: TracerConfig,
+    ) -> anyhow::Result<EvmTrace> {
+        let (result, mut trace) = tracer::trace(config, || self.run_call(call_args));
+        let (info, _) = result?;
+        trace.gas = info.used_gas.standard.low_u64();
+        trace.failed = !matches!(info.exit_reason, ExitReason::Succeed(_));
+        trace.return_value = info.value;
+        Ok(trace)
+    }
+
+    /// Execute a call, measuring the balance changes before any top up of the caller.
+    fn run_call(&mut self, call_args: CallArgs) -> anyhow::Result<(CallInfo, CallCost)> {
+        let CallArgs {
+            source,
+            target,
+            input,
+            value,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            nonce,
+            access_list,
+        } = call_args;
+        self.transact(source, nonce, |nonce| {
+            let balances = || {
+                (
+                    pallet_balances::Pallet::<R>::free_balance(&AccountIdFor::<R>::from(source)),
+                    pallet_balances::Pallet::<R>::free_balance(&AccountIdFor::<R>::from(target)),
+                )
+            };
+            let (caller_before, contract_before) = balances();
             let is_transactional = true;
             let validate = true;
             let info = R::Runner::call(
@@ -188,11 +425,13 @@ where
                 let ser_err = serde_json::to_string_pretty(&err).unwrap();
                 anyhow::anyhow!("error invoking call: {}", ser_err)
             })?;
-            if let ExitReason::Succeed(_) = info.exit_reason {
-                Ok(info.value)
-            } else {
-                Err(anyhow::anyhow!("call failed: {:?}", info.exit_reason))
-            }
+            let (caller_after, contract_after) = balances();
+            let cost = CallCost {
+                storage_deposit: 0,
+                caller_balance: CallCost::delta(caller_before, caller_after),
+                contract_balance: CallCost::delta(contract_before, contract_after),
+            };
+            Ok((info, cost))
         })
     }
 
@@ -206,6 +445,28 @@ where
             .map_err(|_err| anyhow::anyhow!("error minting into account"))
     }
 
+    /// Read the storage slot `key` of the contract at `address`.
+    pub fn read_storage(&mut self, address: H160, key: H256) -> H256 {
+        self.execute_with(|| pallet_evm::AccountStorages::<R>::get(address, key))
+    }
+
+    /// Read all storage slots of the contract at `address`.
+    pub fn dump_storage(&mut self, address: H160) -> StorageDump {
+        self.execute_with(|| StorageDump {
+            entries: pallet_evm::AccountStorages::<R>::iter_prefix(address)
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+                .collect(),
+        })
+    }
+
+    /// Fund the EVM accounts of `accounts` with their configured balances.
+    pub fn fund(&mut self, accounts: &Accounts) -> anyhow::Result<()> {
+        for (address, balance) in accounts.funded_evm_addresses() {
+            self.mint_into(address, balance.into())?;
+        }
+        Ok(())
+    }
+
     /// Return the free balance of an account.
     ///
     /// # Arguments
@@ -217,6 +478,39 @@ where
     }
 }
 
+impl EvmSandbox<EvmRuntime> {
+    /// Set how transaction fees are accounted for.
+    ///
+    /// [`FeeMode::Zero`] is equivalent to disabling [`EvmConfig::charge_fees`].
+    pub fn with_fee_mode(mut self, fee_mode: FeeMode) -> Self {
+        self.fee_mode = fee_mode;
+        let config = self.config().with_charge_fees(fee_mode != FeeMode::Zero);
+        self.with_config(config)
+    }
+
+    /// Set the runtime parameters of the sandbox.
+    pub fn with_config(mut self, config: EvmConfig) -> Self {
+        self.execute_with(|| config.store());
+        self
+    }
+
+    /// The current runtime parameters of the sandbox.
+    pub fn config(&mut self) -> EvmConfig {
+        self.execute_with(EvmConfig::load)
+    }
+}
+
+/// The outcome of a successful EVM call.
+#[derive(Debug, Clone)]
+pub struct CallOutcome {
+    /// The data returned by the contract.
+    pub data: Vec<u8>,
+    /// The gas used by the call.
+    pub used_gas: u64,
+    /// The balance changes caused by the call, before any top up of the caller.
+    pub cost: CallCost,
+}
+
 #[derive(Default)]
 pub struct CreateArgs {
     pub source: H160,
@@ -0,0 +1,87 @@
+//! Precompiles of the [`super::EvmRuntime`].
+//!
+//! Provides the standard Ethereum precompiles at addresses `0x01` to `0x09`, plus any custom
+//! precompiles registered with [`register_precompile`].
+
+use std::rc::Rc;
+
+use pallet_evm::{
+    IsPrecompileResult, Precompile, PrecompileHandle, PrecompileResult, PrecompileSet,
+};
+use pallet_evm_precompile_blake2::Blake2F;
+use pallet_evm_precompile_bn128::{Bn128Add, Bn128Mul, Bn128Pairing};
+use pallet_evm_precompile_modexp::Modexp;
+use pallet_evm_precompile_simple::{ECRecover, Identity, Ripemd160, Sha256};
+use sp_core::H160;
+
+use crate::registry::Registry;
+
+/// A precompile implemented by a closure.
+pub type CustomPrecompile = Rc<dyn Fn(&mut dyn PrecompileHandle) -> PrecompileResult>;
+
+thread_local! {
+    static CUSTOM_PRECOMPILES: Registry<H160, CustomPrecompile> = const { Registry::new() };
+}
+
+/// Register a custom precompile at `address`, replacing any existing precompile at that address.
+///
+/// Custom precompiles are registered for all sandboxes on the current thread.
+pub fn register_precompile<F>(address: H160, precompile: F)
+where
+    F: Fn(&mut dyn PrecompileHandle) -> PrecompileResult + 'static,
+{
+    CUSTOM_PRECOMPILES.with(|precompiles| precompiles.insert(address, Rc::new(precompile)));
+}
+
+/// Remove the custom precompile at `address`, if any.
+pub fn unregister_precompile(address: H160) {
+    CUSTOM_PRECOMPILES.with(|precompiles| precompiles.remove(&address));
+}
+
+fn custom_precompile(address: &H160) -> Option<CustomPrecompile> {
+    CUSTOM_PRECOMPILES.with(|precompiles| precompiles.get(address))
+}
+
+/// The standard Ethereum precompiles followed by the custom precompiles.
+#[derive(Default)]
+pub struct Precompiles;
+
+impl Precompiles {
+    /// The addresses of the standard precompiles.
+    pub fn standard_addresses() -> [H160; 9] {
+        [1, 2, 3, 4, 5, 6, 7, 8, 9].map(hash)
+    }
+}
+
+impl PrecompileSet for Precompiles {
+    fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+        let address = handle.code_address();
+        if let Some(precompile) = custom_precompile(&address) {
+            return Some(precompile(handle));
+        }
+        match address {
+            a if a == hash(1) => Some(ECRecover::execute(handle)),
+            a if a == hash(2) => Some(Sha256::execute(handle)),
+            a if a == hash(3) => Some(Ripemd160::execute(handle)),
+            a if a == hash(4) => Some(Identity::execute(handle)),
+            a if a == hash(5) => Some(Modexp::execute(handle)),
+            a if a == hash(6) => Some(Bn128Add::execute(handle)),
+            a if a == hash(7) => Some(Bn128Mul::execute(handle)),
+            a if a == hash(8) => Some(Bn128Pairing::execute(handle)),
+            a if a == hash(9) => Some(Blake2F::execute(handle)),
+            _ => None,
+        }
+    }
+
+    fn is_precompile(&self, address: H160, _gas: u64) -> IsPrecompileResult {
+        IsPrecompileResult::Answer {
+            is_precompile: Self::standard_addresses().contains(&address)
+                || custom_precompile(&address).is_some(),
+            extra_cost: 0,
+        }
+    }
+}
+
+fn hash(a: u64) -> H160 {
+    H160::from_low_u64_be(a)
+}
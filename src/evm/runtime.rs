@@ -6,9 +6,13 @@ use frame_support::{
 };
 use sp_core::{H160, H256, U256};
 use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
-use sp_std::{boxed::Box, prelude::*, str::FromStr};
+use sp_std::{boxed::Box, prelude::*};
 
-use pallet_evm::{EnsureAddressNever, EnsureAddressRoot, FeeCalculator, IdentityAddressMapping};
+use super::precompiles::Precompiles;
+use pallet_evm::{
+    EnsureAddressNever, EnsureAddressRoot, FeeCalculator, IdentityAddressMapping,
+    OnChargeEVMTransaction,
+};
 
 frame_support::construct_runtime! {
     pub enum EvmRuntime {
@@ -81,30 +85,172 @@ impl pallet_timestamp::Config for EvmRuntime {
     type WeightInfo = ();
 }
 
-pub struct FixedGasPrice;
-impl FeeCalculator for FixedGasPrice {
+const DEFAULT_MIN_GAS_PRICE: u128 = 1_000_000_000;
+const DEFAULT_WEIGHT_PER_GAS: u64 = 20_000;
+const DEFAULT_BLOCK_AUTHOR: H160 = H160([
+    0x12, 0x34, 0x50, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+]);
+
+parameter_types! {
+    pub storage ChainId: u64 = 0;
+    pub storage MinGasPrice: U256 = U256::from(DEFAULT_MIN_GAS_PRICE);
+    pub storage WeightPerGas: Weight = Weight::from_parts(DEFAULT_WEIGHT_PER_GAS, 0);
+    pub storage BlockGasLimit: U256 = U256::max_value();
+    pub storage BlockAuthor: H160 = DEFAULT_BLOCK_AUTHOR;
+    pub storage ChargeFees: bool = true;
+    pub SuicideQuickClearLimit: u32 = 0;
+    pub PrecompilesValue: Precompiles = Precompiles;
+}
+
+/// The parameters of the [`EvmRuntime`].
+///
+/// The parameters are kept in storage, so they are set per sandbox. The defaults are chosen for
+/// benchmarking rather than to match any particular chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmConfig {
+    /// The chain id returned by the `CHAINID` opcode.
+    pub chain_id: u64,
+    /// The minimum gas price, which is also the base fee.
+    pub min_gas_price: U256,
+    /// The weight charged per unit of gas.
+    pub weight_per_gas: Weight,
+    /// The maximum amount of gas per block.
+    pub block_gas_limit: U256,
+    /// The block author, returned by the `COINBASE` opcode and receiving priority fees.
+    pub block_author: H160,
+    /// Whether transaction fees are withdrawn from the caller.
+    ///
+    /// Note that the caller still requires enough balance to cover `gas_limit * max_fee_per_gas`
+    /// for the transaction to be valid, unless the gas price is zero.
+    pub charge_fees: bool,
+}
+
+impl Default for EvmConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: 0,
+            min_gas_price: U256::from(DEFAULT_MIN_GAS_PRICE),
+            weight_per_gas: Weight::from_parts(DEFAULT_WEIGHT_PER_GAS, 0),
+            block_gas_limit: U256::max_value(),
+            block_author: DEFAULT_BLOCK_AUTHOR,
+            charge_fees: true,
+        }
+    }
+}
+
+impl EvmConfig {
+    /// Read the parameters from storage. Must be called within externalities.
+    pub fn load() -> Self {
+        Self {
+            chain_id: ChainId::get(),
+            min_gas_price: MinGasPrice::get(),
+            weight_per_gas: WeightPerGas::get(),
+            block_gas_limit: BlockGasLimit::get(),
+            block_author: BlockAuthor::get(),
+            charge_fees: ChargeFees::get(),
+        }
+    }
+
+    /// Write the parameters to storage. Must be called within externalities.
+    pub fn store(&self) {
+        ChainId::set(&self.chain_id);
+        MinGasPrice::set(&self.min_gas_price);
+        WeightPerGas::set(&self.weight_per_gas);
+        BlockGasLimit::set(&self.block_gas_limit);
+        BlockAuthor::set(&self.block_author);
+        ChargeFees::set(&self.charge_fees);
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    pub fn with_min_gas_price(mut self, min_gas_price: U256) -> Self {
+        self.min_gas_price = min_gas_price;
+        self
+    }
+
+    pub fn with_weight_per_gas(mut self, weight_per_gas: Weight) -> Self {
+        self.weight_per_gas = weight_per_gas;
+        self
+    }
+
+    pub fn with_block_gas_limit(mut self, block_gas_limit: U256) -> Self {
+        self.block_gas_limit = block_gas_limit;
+        self
+    }
+
+    pub fn with_block_author(mut self, block_author: H160) -> Self {
+        self.block_author = block_author;
+        self
+    }
+
+    pub fn with_charge_fees(mut self, charge_fees: bool) -> Self {
+        self.charge_fees = charge_fees;
+        self
+    }
+}
+
+pub struct ConfigurableGasPrice;
+impl FeeCalculator for ConfigurableGasPrice {
     fn min_gas_price() -> (U256, Weight) {
-        // Return some meaningful gas price and weight
-        (1_000_000_000u128.into(), Weight::from_parts(7u64, 0))
+        (MinGasPrice::get(), Weight::from_parts(7u64, 0))
     }
 }
 
-pub struct FindAuthorTruncated;
-impl FindAuthor<H160> for FindAuthorTruncated {
+pub struct ConfigurableAuthor;
+impl FindAuthor<H160> for ConfigurableAuthor {
     fn find_author<'a, I>(_digests: I) -> Option<H160>
     where
         I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
     {
-        Some(H160::from_str("1234500000000000000000000000000000000000").unwrap())
+        Some(BlockAuthor::get())
     }
 }
-parameter_types! {
-    pub BlockGasLimit: U256 = U256::max_value();
-    pub WeightPerGas: Weight = Weight::from_parts(20_000, 0);
-    pub SuicideQuickClearLimit: u32 = 0;
+
+/// Charges transaction fees like the default `pallet-evm` currency adapter, unless disabled via
+/// [`EvmConfig::charge_fees`].
+pub struct ConfigurableFees;
+impl OnChargeEVMTransaction<EvmRuntime> for ConfigurableFees {
+    type LiquidityInfo = <() as OnChargeEVMTransaction<EvmRuntime>>::LiquidityInfo;
+
+    fn withdraw_fee(
+        who: &H160,
+        fee: U256,
+    ) -> Result<Self::LiquidityInfo, pallet_evm::Error<EvmRuntime>> {
+        if ChargeFees::get() {
+            <() as OnChargeEVMTransaction<EvmRuntime>>::withdraw_fee(who, fee)
+        } else {
+            Ok(Default::default())
+        }
+    }
+
+    fn correct_and_deposit_fee(
+        who: &H160,
+        corrected_fee: U256,
+        base_fee: U256,
+        already_withdrawn: Self::LiquidityInfo,
+    ) -> Self::LiquidityInfo {
+        if ChargeFees::get() {
+            <() as OnChargeEVMTransaction<EvmRuntime>>::correct_and_deposit_fee(
+                who,
+                corrected_fee,
+                base_fee,
+                already_withdrawn,
+            )
+        } else {
+            already_withdrawn
+        }
+    }
+
+    fn pay_priority_fee(tip: Self::LiquidityInfo) {
+        <() as OnChargeEVMTransaction<EvmRuntime>>::pay_priority_fee(tip)
+    }
 }
+
 impl pallet_evm::Config for EvmRuntime {
-    type FeeCalculator = FixedGasPrice;
+    type FeeCalculator = ConfigurableGasPrice;
     type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
     type WeightPerGas = WeightPerGas;
 
@@ -116,14 +262,14 @@ impl pallet_evm::Config for EvmRuntime {
     type Currency = Balances;
 
     type RuntimeEvent = RuntimeEvent;
-    type PrecompilesType = ();
-    type PrecompilesValue = ();
-    type ChainId = ();
+    type PrecompilesType = Precompiles;
+    type PrecompilesValue = PrecompilesValue;
+    type ChainId = ChainId;
     type BlockGasLimit = BlockGasLimit;
     type Runner = pallet_evm::runner::stack::Runner<Self>;
-    type OnChargeTransaction = ();
+    type OnChargeTransaction = ConfigurableFees;
     type OnCreate = ();
-    type FindAuthor = FindAuthorTruncated;
+    type FindAuthor = ConfigurableAuthor;
     type SuicideQuickClearLimit = SuicideQuickClearLimit;
     type GasLimitPovSizeRatio = ();
     type Timestamp = Timestamp;
@@ -0,0 +1,344 @@
+//! Opcode level tracing of EVM execution.
+//!
+//! Hooks into the tracing listeners of the `evm` crates to record every executed step together with
+//! its gas cost. The resulting [`EvmTrace`] serializes to the shape of the geth `structLogs` tracer
+//! output, so it can be inspected with existing tooling.
+
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use serde::Serialize;
+use sp_core::U256;
+
+/// Configuration of the data recorded for every step.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracerConfig {
+    /// Record the stack for each step.
+    pub enable_stack: bool,
+    /// Record the memory for each step. This is expensive for memory heavy contracts.
+    pub enable_memory: bool,
+    /// Only aggregate the opcode statistics, without recording individual steps.
+    pub disable_steps: bool,
+}
+
+/// The trace of a single EVM call.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmTrace {
+    /// The gas used by the call.
+    pub gas: u64,
+    /// Whether the call failed.
+    pub failed: bool,
+    /// The data returned by the call.
+    #[serde(serialize_with = "serialize_hex")]
+    pub return_value: Vec<u8>,
+    /// The executed steps.
+    pub struct_logs: Vec<StructLog>,
+    /// Execution count and gas cost per opcode, not part of the geth format.
+    #[serde(skip)]
+    pub opcodes: BTreeMap<&'static str, OpcodeStats>,
+}
+
+/// A single executed step, following the geth `structLogs` format.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: u64,
+    pub op: &'static str,
+    /// The gas remaining before executing the step.
+    pub gas: u64,
+    /// The gas charged for the step, including memory expansion.
+    pub gas_cost: u64,
+    /// The call depth, starting at 1.
+    pub depth: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+}
+
+/// Execution statistics of a single opcode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeStats {
+    /// The number of times the opcode was executed.
+    pub count: u64,
+    /// The total gas charged for the opcode.
+    pub gas: u64,
+}
+
+impl EvmTrace {
+    /// Serialize the trace to geth `structLogs` compatible JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("EvmTrace serialization is infallible")
+    }
+
+    /// The opcodes ordered by total gas cost, most expensive first.
+    pub fn opcodes_by_gas(&self) -> Vec<(&'static str, OpcodeStats)> {
+        let mut opcodes = self
+            .opcodes
+            .iter()
+            .map(|(op, stats)| (*op, *stats))
+            .collect::<Vec<_>>();
+        opcodes.sort_by(|(_, a), (_, b)| b.gas.cmp(&a.gas));
+        opcodes
+    }
+}
+
+/// Execute `f` while recording an [`EvmTrace`] of all EVM execution within it.
+///
+/// The gas used, failure status and return value of the trace are left at their defaults, to be
+/// filled in by the caller from the result of the call.
+pub fn trace<T>(config: TracerConfig, f: impl FnOnce() -> T) -> (T, EvmTrace) {
+    let state = Rc::new(RefCell::new(TracerState {
+        config,
+        ..Default::default()
+    }));
+
+    let mut runtime_listener = RuntimeListener(state.clone());
+    let mut gasometer_listener = GasometerListener(state.clone());
+    let mut evm_listener = EvmListener(state.clone());
+
+    let result = evm::tracing::using(&mut evm_listener, || {
+        evm_gasometer::tracing::using(&mut gasometer_listener, || {
+            evm_runtime::tracing::using(&mut runtime_listener, f)
+        })
+    });
+
+    let mut state = state.take();
+    state.finish_step();
+    let trace = EvmTrace {
+        struct_logs: state.struct_logs,
+        opcodes: state.opcodes,
+        ..Default::default()
+    };
+    (result, trace)
+}
+
+#[derive(Default)]
+struct TracerState {
+    config: TracerConfig,
+    depth: u32,
+    struct_logs: Vec<StructLog>,
+    opcodes: BTreeMap<&'static str, OpcodeStats>,
+    /// The step currently being executed, which gas costs are attributed to.
+    current: Option<StructLog>,
+    /// Whether the remaining gas of the current step has been recorded.
+    current_gas_known: bool,
+}
+
+impl TracerState {
+    fn step(&mut self, log: StructLog) {
+        self.finish_step();
+        self.opcodes.entry(log.op).or_default().count += 1;
+        self.current = Some(log);
+        self.current_gas_known = false;
+    }
+
+    fn record_cost(&mut self, cost: u64, snapshot: Option<evm_gasometer::tracing::Snapshot>) {
+        let Some(current) = self.current.as_mut() else {
+            // transaction costs are charged before the first step
+            return;
+        };
+        if let (false, Some(snapshot)) = (self.current_gas_known, snapshot) {
+            current.gas = snapshot
+                .gas_limit
+                .saturating_sub(snapshot.used_gas)
+                .saturating_sub(snapshot.memory_gas);
+            self.current_gas_known = true;
+        }
+        current.gas_cost += cost;
+        self.opcodes.entry(current.op).or_default().gas += cost;
+    }
+
+    fn finish_step(&mut self) {
+        if let Some(log) = self.current.take() {
+            if !self.config.disable_steps {
+                self.struct_logs.push(log);
+            }
+        }
+    }
+}
+
+struct RuntimeListener(Rc<RefCell<TracerState>>);
+
+impl evm_runtime::tracing::EventListener for RuntimeListener {
+    fn event(&mut self, event: evm_runtime::tracing::Event) {
+        if let evm_runtime::tracing::Event::Step {
+            opcode,
+            position,
+            stack,
+            memory,
+            ..
+        } = event
+        {
+            let mut state = self.0.borrow_mut();
+            let Ok(pc) = position else { return };
+            let stack = state.config.enable_stack.then(|| {
+                stack
+                    .data()
+                    .iter()
+                    .map(|word| format!("{:#x}", U256::from_big_endian(word.as_bytes())))
+                    .collect()
+            });
+            let memory = state
+                .config
+                .enable_memory
+                .then(|| memory.data().chunks(32).map(hex::encode).collect());
+            let log = StructLog {
+                pc: *pc as u64,
+                op: opcode_name(opcode.0),
+                depth: state.depth,
+                stack,
+                memory,
+                ..Default::default()
+            };
+            state.step(log);
+        }
+    }
+}
+
+struct GasometerListener(Rc<RefCell<TracerState>>);
+
+impl evm_gasometer::tracing::EventListener for GasometerListener {
+    fn event(&mut self, event: evm_gasometer::tracing::Event) {
+        use evm_gasometer::tracing::Event;
+
+        let mut state = self.0.borrow_mut();
+        match event {
+            Event::RecordCost { cost, snapshot } => state.record_cost(cost, snapshot),
+            Event::RecordDynamicCost {
+                gas_cost,
+                memory_gas,
+                snapshot,
+                ..
+            } => {
+                // `memory_gas` is the total memory cost, only the expansion is charged
+                let expansion = snapshot
+                    .map(|snapshot| memory_gas.saturating_sub(snapshot.memory_gas))
+                    .unwrap_or_default();
+                state.record_cost(gas_cost + expansion, snapshot)
+            }
+            _ => (),
+        }
+    }
+}
+
+struct EvmListener(Rc<RefCell<TracerState>>);
+
+impl evm::tracing::EventListener for EvmListener {
+    fn event(&mut self, event: evm::tracing::Event) {
+        use evm::tracing::Event;
+
+        let mut state = self.0.borrow_mut();
+        match event {
+            Event::Call { .. } | Event::Create { .. } => state.depth += 1,
+            Event::Exit { .. } => state.depth = state.depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+}
+
+/// The mnemonic of an EVM opcode, as used by geth.
+pub fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5f => "PUSH0",
+        0x60..=0x7f => PUSH[(opcode - 0x60) as usize],
+        0x80..=0x8f => DUP[(opcode - 0x80) as usize],
+        0x90..=0x9f => SWAP[(opcode - 0x90) as usize],
+        0xa0..=0xa4 => LOG[(opcode - 0xa0) as usize],
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => "UNKNOWN",
+    }
+}
+
+const PUSH: [&str; 32] = [
+    "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+    "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+    "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+    "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+];
+const DUP: [&str; 16] = [
+    "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+    "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+];
+const SWAP: [&str; 16] = [
+    "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+    "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+];
+const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+fn serialize_hex<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
@@ -27,6 +27,7 @@ use subxt_signer::sr25519::{dev, Keypair};
 #[allow(clippy::new_without_default)]
 pub struct InkDrink<E: Environment, Runtime: RuntimeT> {
     pub drink: DrinkApi<Runtime>,
+    build_options: InkBuildOptions,
     _phantom: PhantomData<E>,
 }
 
@@ -43,10 +44,21 @@ where
         let drink = DrinkApi::new();
         Self {
             drink,
+            build_options: InkBuildOptions::default(),
             _phantom: PhantomData,
         }
     }
 
+    /// Build contracts with `build_options` instead of the defaults.
+    pub fn with_build_options(mut self, build_options: InkBuildOptions) -> Self {
+        self.build_options = build_options;
+        self
+    }
+
+    pub fn build_options(&self) -> &InkBuildOptions {
+        &self.build_options
+    }
+
     pub fn build_and_instantiate<P, Contract, Args, R>(
         &mut self,
         contract: P,
@@ -55,28 +67,131 @@ where
     where
         P: AsRef<Path> + Copy,
         Contract: ContractReference + ContractCallBuilder,
-        <Contract as ContractReference>::Type: Clone,
+        <Contract as ContractReference>::Type: Clone + FromAccountId<E>,
+        <Contract as ContractCallBuilder>::Type: FromAccountId<E>,
+        Args: Encode + Clone,
+    {
+        self.build_and_instantiate_with::<_, Contract, _, _>(
+            contract,
+            constructor,
+            InstantiateOptions::new(),
+        )
+        .call_builder
+    }
+
+    /// Build the contract and instantiate it with any of its constructors, e.g.
+    /// `PreloadRef::with_entries(1000)`, with the given options.
+    pub fn build_and_instantiate_with<P, Contract, Args, R>(
+        &mut self,
+        contract: P,
+        constructor: &mut CreateBuilderPartial<E, <Contract as ContractReference>::Type, Args, R>,
+        options: InstantiateOptions<Runtime>,
+    ) -> Instantiated<E, Contract, Runtime>
+    where
+        P: AsRef<Path> + Copy,
+        Contract: ContractReference + ContractCallBuilder,
+        <Contract as ContractReference>::Type: Clone + FromAccountId<E>,
         <Contract as ContractCallBuilder>::Type: FromAccountId<E>,
         Args: Encode + Clone,
     {
-        let target = crate::target();
-        let build_result = build_contract(contract, target).expect("Error building contract");
-        let code = std::fs::read(build_result).expect("Error loading contract");
+        let build_result = build_contract_with_options(contract, &self.build_options)
+            .expect("Error building contract");
+        let code = std::fs::read(build_result.code).expect("Error loading contract");
 
-        let caller = dev::alice();
+        let InstantiateOptions {
+            caller,
+            value,
+            salt,
+            storage_deposit_limit,
+        } = options;
         let data = constructor_exec_input(constructor.clone());
-        let create_args = CreateArgs::new(code, caller).with_data(data);
+        let create_args = CreateArgs::new(code, caller)
+            .with_data(data)
+            .with_value(value)
+            .with_salt(salt)
+            .with_storage_deposit_limit(storage_deposit_limit);
 
         let account_id = self
             .drink
             .instantiate_with_code(create_args)
             .expect("Error instantiating contract");
-        <<Contract as ContractCallBuilder>::Type as FromAccountId<E>>::from_account_id(
-            E::AccountId::from(*account_id.as_ref()),
-        )
+        let ink_account_id = E::AccountId::from(*account_id.as_ref());
+        let contract_ref = FromAccountId::<E>::from_account_id(ink_account_id.clone());
+        let call_builder = FromAccountId::<E>::from_account_id(ink_account_id);
+        Instantiated {
+            account_id,
+            contract_ref,
+            call_builder,
+            _phantom: PhantomData,
+        }
     }
 }
 
+/// Options for instantiating an ink! contract with [`InkDrink::build_and_instantiate_with`].
+pub struct InstantiateOptions<Runtime: RuntimeT + pallet_contracts::Config> {
+    pub caller: Keypair,
+    pub value: ContractsBalanceOf<Runtime>,
+    pub salt: Vec<u8>,
+    pub storage_deposit_limit: Option<ContractsBalanceOf<Runtime>>,
+}
+
+impl<Runtime: RuntimeT + pallet_contracts::Config> InstantiateOptions<Runtime>
+where
+    ContractsBalanceOf<Runtime>: From<u128>,
+{
+    /// Instantiate as `alice` without value, salt or storage deposit limit.
+    pub fn new() -> Self {
+        Self {
+            caller: dev::alice(),
+            value: 0u128.into(),
+            salt: Vec::new(),
+            storage_deposit_limit: None,
+        }
+    }
+}
+
+impl<Runtime: RuntimeT + pallet_contracts::Config> InstantiateOptions<Runtime> {
+    pub fn with_caller(mut self, caller: Keypair) -> Self {
+        self.caller = caller;
+        self
+    }
+
+    /// Set the value transferred to the contract, requires a `payable` constructor.
+    pub fn with_value(mut self, value: ContractsBalanceOf<Runtime>) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn with_salt(mut self, salt: Vec<u8>) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    pub fn with_storage_deposit_limit(
+        mut self,
+        storage_deposit_limit: ContractsBalanceOf<Runtime>,
+    ) -> Self {
+        self.storage_deposit_limit = Some(storage_deposit_limit);
+        self
+    }
+}
+
+/// An instantiated ink! contract.
+pub struct Instantiated<E, Contract, Runtime>
+where
+    E: Environment,
+    Contract: ContractReference + ContractCallBuilder,
+    Runtime: RuntimeT,
+{
+    /// The account the contract is instantiated at.
+    pub account_id: AccountIdFor<Runtime>,
+    /// The typed contract reference, e.g. `FlipperRef`.
+    pub contract_ref: <Contract as ContractReference>::Type,
+    /// The call builder, to create the call args of messages.
+    pub call_builder: <Contract as ContractCallBuilder>::Type,
+    _phantom: PhantomData<E>,
+}
+
 impl<Runtime: RuntimeT + pallet_contracts::Config> CallArgs<Runtime> {
     pub fn from_call_builder<E: Environment, Args: Encode + Clone, RetType: Decode>(
         caller: Keypair,
@@ -134,9 +249,133 @@ pub type CallBuilderFinal<E, Args, RetType> = ink::env::call::CallBuilder<
     Set<ReturnType<RetType>>,
 >;
 
+/// Options for building an ink! contract with [`build_contract_with_options`].
+///
+/// The default is a release build for the target selected by the crate features, producing only
+/// the optimized code blob.
+#[derive(Clone, Debug)]
+pub struct InkBuildOptions {
+    pub target: Target,
+    pub build_mode: BuildMode,
+    /// Cargo features of the contract to enable.
+    pub features: Vec<String>,
+    pub network: Network,
+    /// The `wasm-opt` optimization level, ignored for RISC-V.
+    pub optimization_passes: OptimizationPasses,
+    /// Keep the debug symbols in the code blob, for use with [`crate::profile::CodeProfile`].
+    pub keep_debug_symbols: bool,
+    /// Whether to produce only the code blob, or also the metadata and `.contract` bundle.
+    pub build_artifacts: BuildArtifacts,
+}
+
+impl Default for InkBuildOptions {
+    fn default() -> Self {
+        Self {
+            target: crate::target(),
+            build_mode: BuildMode::Release,
+            features: Vec::new(),
+            network: Network::Online,
+            optimization_passes: OptimizationPasses::default(),
+            keep_debug_symbols: false,
+            build_artifacts: BuildArtifacts::CodeOnly,
+        }
+    }
+}
+
+impl InkBuildOptions {
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn with_build_mode(mut self, build_mode: BuildMode) -> Self {
+        self.build_mode = build_mode;
+        self
+    }
+
+    /// Enable the cargo feature `feature` of the contract.
+    pub fn with_feature(mut self, feature: &str) -> Self {
+        self.features.push(feature.to_string());
+        self
+    }
+
+    /// Build without network access, all dependencies must already be available locally.
+    pub fn offline(mut self) -> Self {
+        self.network = Network::Offline;
+        self
+    }
+
+    pub fn with_optimization_passes(mut self, optimization_passes: OptimizationPasses) -> Self {
+        self.optimization_passes = optimization_passes;
+        self
+    }
+
+    pub fn with_debug_symbols(mut self, keep_debug_symbols: bool) -> Self {
+        self.keep_debug_symbols = keep_debug_symbols;
+        self
+    }
+
+    /// Also generate the metadata and `.contract` bundle.
+    pub fn with_metadata(mut self) -> Self {
+        self.build_artifacts = BuildArtifacts::All;
+        self
+    }
+}
+
 /// Builds the contract at `manifest_path`, returns the path to the contract
 /// Wasm build artifact.
 pub fn build_contract<P>(path_to_cargo_toml: P, target: Target) -> anyhow::Result<PathBuf>
+where
+    P: AsRef<Path> + Copy,
+{
+    let options = InkBuildOptions::default().with_target(target);
+    build_contract_with_options(path_to_cargo_toml, &options).map(|artifacts| artifacts.code)
+}
+
+/// Builds the contract at `manifest_path` keeping the debug symbols in the code blob, for use
+/// with [`crate::profile::CodeProfile`]. Returns the path to the contract code build artifact.
+pub fn build_contract_with_debug_symbols<P>(
+    path_to_cargo_toml: P,
+    target: Target,
+) -> anyhow::Result<PathBuf>
+where
+    P: AsRef<Path> + Copy,
+{
+    let options = InkBuildOptions::default()
+        .with_target(target)
+        .with_debug_symbols(true);
+    build_contract_with_options(path_to_cargo_toml, &options).map(|artifacts| artifacts.code)
+}
+
+/// Builds the contract at `manifest_path` together with its metadata, for use with
+/// [`crate::ink_metadata::InkMetadataContract`].
+pub fn build_contract_with_metadata<P>(
+    path_to_cargo_toml: P,
+    target: Target,
+) -> anyhow::Result<BuildArtifactPaths>
+where
+    P: AsRef<Path> + Copy,
+{
+    let options = InkBuildOptions::default()
+        .with_target(target)
+        .with_metadata();
+    build_contract_with_options(path_to_cargo_toml, &options)
+}
+
+/// The paths to the artifacts of a contract build.
+#[derive(Debug, Clone)]
+pub struct BuildArtifactPaths {
+    /// The contract code blob.
+    pub code: PathBuf,
+    /// The `.contract` bundle of code and metadata, if metadata was generated.
+    pub bundle: Option<PathBuf>,
+}
+
+/// Builds the contract at `manifest_path` with the given `options`.
+pub fn build_contract_with_options<P>(
+    path_to_cargo_toml: P,
+    options: &InkBuildOptions,
+) -> anyhow::Result<BuildArtifactPaths>
 where
     P: AsRef<Path> + Copy,
 {
@@ -146,19 +385,23 @@ where
             path_to_cargo_toml.as_ref().display()
         )
     });
+    let mut features = Features::default();
+    for feature in &options.features {
+        features.push(feature);
+    }
     let args = ExecuteArgs {
         manifest_path,
         verbosity: Verbosity::Default,
-        build_mode: BuildMode::Release,
-        features: Features::default(),
-        network: Network::Online,
-        build_artifact: BuildArtifacts::CodeOnly,
+        build_mode: options.build_mode,
+        features,
+        network: options.network,
+        build_artifact: options.build_artifacts,
         unstable_flags: UnstableFlags::default(),
-        optimization_passes: Some(OptimizationPasses::default()),
-        keep_debug_symbols: false,
+        optimization_passes: Some(options.optimization_passes),
+        keep_debug_symbols: options.keep_debug_symbols,
         output_type: OutputType::HumanReadable,
         skip_wasm_validation: false,
-        target,
+        target: options.target,
         ..Default::default()
     };
 
@@ -168,6 +411,13 @@ where
         .dest_wasm
         .expect("Wasm code artifact not generated")
         .canonicalize()?;
+    let bundle_path = build_result
+        .metadata_result
+        .map(|metadata| metadata.dest_bundle.canonicalize())
+        .transpose()?;
 
-    Ok(code_artifact_path)
+    Ok(BuildArtifactPaths {
+        code: code_artifact_path,
+        bundle: bundle_path,
+    })
 }
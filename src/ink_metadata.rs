@@ -0,0 +1,192 @@
+//! Calling ink! contracts through their metadata, without linking the contract crate.
+//!
+//! [`InkDrink::build_and_instantiate_from_metadata`] builds the contract together with its
+//! `.contract` bundle. Constructors and messages are then selected by label, their arguments are
+//! given as JSON values and encoded with `contract-transcode`, and return values are decoded the
+//! same way. So new contracts can be benchmarked by their directory alone.
+
+use std::path::Path;
+
+use contract_transcode::{ContractMessageTranscoder, Value as SconValue};
+use ink::env::Environment;
+use serde_json::Value;
+use subxt_signer::sr25519::Keypair;
+
+use crate::{
+    drink::{
+        pallet_balances, pallet_contracts,
+        runtime::{AccountIdFor, Runtime as RuntimeT},
+        BalanceOf,
+    },
+    drink_api::{CallArgs, ContractsBalanceOf, CreateArgs},
+    ink::{build_contract_with_options, InkDrink, InstantiateOptions},
+};
+
+/// An ink! contract instantiated from its metadata, see
+/// [`InkDrink::build_and_instantiate_from_metadata`].
+pub struct InkMetadataContract<Runtime: RuntimeT> {
+    account_id: AccountIdFor<Runtime>,
+    transcoder: ContractMessageTranscoder,
+}
+
+impl<Runtime> InkMetadataContract<Runtime>
+where
+    Runtime: RuntimeT + pallet_contracts::Config,
+{
+    /// The account the contract is instantiated at.
+    pub fn account_id(&self) -> AccountIdFor<Runtime> {
+        self.account_id.clone()
+    }
+
+    /// Create the call args for the message `message` with the JSON encoded `args`.
+    pub fn call_args(
+        &self,
+        caller: Keypair,
+        message: &str,
+        args: &[Value],
+    ) -> anyhow::Result<CallArgs<Runtime>>
+    where
+        ContractsBalanceOf<Runtime>: From<u128>,
+    {
+        let call_data = encode(&self.transcoder, message, args)?;
+        Ok(
+            CallArgs::<Runtime>::new(self.account_id.clone(), caller, call_data)
+                .with_max_gas_limit(),
+        )
+    }
+
+    /// Decode the data returned from a call of `message`.
+    pub fn decode_return(&self, message: &str, mut data: &[u8]) -> anyhow::Result<SconValue> {
+        self.transcoder
+            .decode_message_return(message, &mut data)
+            .map_err(|err| anyhow::anyhow!("Error decoding return value of {message}: {err}"))
+    }
+}
+
+impl<E, Runtime> InkDrink<E, Runtime>
+where
+    E: Environment,
+    E::AccountId: Clone + Send + Sync + From<[u8; 32]> + AsRef<[u8; 32]>,
+    Runtime: RuntimeT + pallet_balances::Config + pallet_contracts::Config,
+    AccountIdFor<Runtime>: From<[u8; 32]> + AsRef<[u8; 32]>,
+    BalanceOf<Runtime>: From<u128> + Into<u128>,
+    ContractsBalanceOf<Runtime>: From<u128> + Into<u128>,
+{
+    /// Build the contract at `contract` with its metadata, using the build options of `self`, and
+    /// instantiate it with the constructor labelled `constructor` and the JSON encoded `args`.
+    pub fn build_and_instantiate_from_metadata<P>(
+        &mut self,
+        contract: P,
+        constructor: &str,
+        args: &[Value],
+        options: InstantiateOptions<Runtime>,
+    ) -> anyhow::Result<InkMetadataContract<Runtime>>
+    where
+        P: AsRef<Path> + Copy,
+    {
+        let build_options = self.build_options().clone().with_metadata();
+        let artifacts = build_contract_with_options(contract, &build_options)?;
+        let bundle = artifacts
+            .bundle
+            .ok_or_else(|| anyhow::anyhow!("Contract metadata not generated"))?;
+        let transcoder = ContractMessageTranscoder::load(bundle)?;
+        let code = std::fs::read(artifacts.code)?;
+
+        let InstantiateOptions {
+            caller,
+            value,
+            salt,
+            storage_deposit_limit,
+        } = options;
+        let data = encode(&transcoder, constructor, args)?;
+        let create_args = CreateArgs::new(code, caller)
+            .with_data(data)
+            .with_value(value)
+            .with_salt(salt)
+            .with_storage_deposit_limit(storage_deposit_limit);
+        let account_id = self.drink.instantiate_with_code(create_args)?;
+
+        Ok(InkMetadataContract {
+            account_id,
+            transcoder,
+        })
+    }
+
+    /// Call the message `message` of `contract` with the JSON encoded `args`, returning the
+    /// decoded result.
+    pub fn call_from_metadata(
+        &mut self,
+        contract: &InkMetadataContract<Runtime>,
+        caller: Keypair,
+        message: &str,
+        args: &[Value],
+    ) -> anyhow::Result<SconValue> {
+        let call_args = contract.call_args(caller, message, args)?;
+        let data = self.drink.call(call_args)?;
+        contract.decode_return(message, &data)
+    }
+}
+
+/// Encode the constructor or message `label` with the JSON encoded `args`.
+fn encode(
+    transcoder: &ContractMessageTranscoder,
+    label: &str,
+    args: &[Value],
+) -> anyhow::Result<Vec<u8>> {
+    let args = args.iter().map(json_to_scon).collect::<Vec<_>>();
+    transcoder
+        .encode(label, args)
+        .map_err(|err| anyhow::anyhow!("Error encoding {label}: {err}"))
+}
+
+/// Convert a JSON value into the SCON notation `contract-transcode` parses arguments from.
+///
+/// Objects become anonymous structs and `null` becomes `None`. Strings starting with `0x` are
+/// passed through unquoted, as SCON parses them as hex encoded bytes, all other strings become
+/// string literals.
+fn json_to_scon(value: &Value) -> String {
+    match value {
+        Value::Null => "None".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) if value.starts_with("0x") => value.clone(),
+        Value::String(value) => {
+            // SCON strings only support escaping quotes and backslashes
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{escaped}\"")
+        }
+        Value::Array(values) => {
+            let values = values.iter().map(json_to_scon).collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Object(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", json_to_scon(value)))
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_json_to_scon() {
+        let value = serde_json::json!({
+            "count": 3,
+            "enabled": true,
+            "name": "schlau",
+            "quoted": "a \"b\" \\ é",
+            "hash": "0x0102",
+            "items": [1, 2],
+            "limit": null
+        });
+        assert_eq!(
+            json_to_scon(&value),
+            r#"{ count: 3, enabled: true, hash: 0x0102, items: [1, 2], limit: None, name: "schlau", quoted: "a \"b\" \\ é" }"#
+        );
+    }
+}
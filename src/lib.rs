@@ -6,11 +6,25 @@ use contract_build::Target;
 pub use drink_riscv as drink;
 #[cfg(feature = "wasm")]
 pub use drink_wasm as drink;
+pub mod accounts;
+pub mod build;
+pub mod contracts;
+pub mod cost;
+pub mod diagnostics;
+pub mod differential;
 pub mod drink_api;
 pub mod evm;
 pub mod ink;
+pub mod ink_metadata;
+pub mod logging;
+pub mod profile;
+mod registry;
 pub mod solang;
 pub mod solc;
+pub mod solidity_imports;
+pub mod storage;
+pub mod strace;
+pub mod toolchain;
 
 pub const fn target() -> Target {
     if cfg!(feature = "wasm") {
@@ -28,3 +42,10 @@ pub const fn target_str() -> &'static str {
         Target::RiscV => "riscv",
     }
 }
+
+/// The directory build artifacts are written to, `CARGO_TARGET_DIR` or `target`.
+pub fn target_dir() -> std::path::PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(Into::into)
+        .unwrap_or_else(|| "target".into())
+}
@@ -0,0 +1,238 @@
+//! Logging setup for binaries and tests, and per call log capture.
+//!
+//! schlau never installs a global subscriber on its own. Binaries call [`LogArgs::init`], tests may
+//! call [`init_for_tests`], and library users are free to install their own subscriber.
+//!
+//! Runtime logs are emitted via the `log` crate under the [`CONTRACTS_TARGET`] and [`EVM_TARGET`]
+//! targets, and forwarded to `tracing`. Records of all levels are forwarded, and the subscribers
+//! decide what is enabled, so the maximum level of the `log` crate is only set once, when the
+//! forwarding is installed.
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::MakeWriter,
+    layer::{Filter, Identity, SubscriberExt},
+    EnvFilter, Layer, Registry,
+};
+
+/// The log target of `pallet-contracts`.
+pub const CONTRACTS_TARGET: &str = "runtime::contracts";
+/// The log target of `pallet-evm` and the `evm` interpreter.
+pub const EVM_TARGET: &str = "evm";
+
+/// The filter applied if neither `RUST_LOG` nor any other directives are set.
+pub const DEFAULT_FILTER: &str = "warn";
+
+/// Logging options, for inclusion in a `clap` command line.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct LogArgs {
+    /// Filter directives in `env_logger` syntax, e.g. `info,runtime::contracts=debug`.
+    #[arg(long = "log", env = "RUST_LOG")]
+    pub filter: Option<String>,
+    /// The level of `pallet-contracts` logs, overriding `--log`.
+    #[arg(long)]
+    pub contracts_log: Option<tracing::Level>,
+    /// The level of `pallet-evm` and EVM interpreter logs, overriding `--log`.
+    #[arg(long)]
+    pub evm_log: Option<tracing::Level>,
+}
+
+impl LogArgs {
+    /// Read the filter directives from the `RUST_LOG` environment variable.
+    pub fn from_env() -> Self {
+        Self {
+            filter: std::env::var(EnvFilter::DEFAULT_ENV).ok(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_contracts_log(mut self, level: tracing::Level) -> Self {
+        self.contracts_log = Some(level);
+        self
+    }
+
+    pub fn with_evm_log(mut self, level: tracing::Level) -> Self {
+        self.evm_log = Some(level);
+        self
+    }
+
+    /// The filter directives, including the per target overrides.
+    pub fn directives(&self) -> String {
+        let mut directives = vec![self
+            .filter
+            .clone()
+            .unwrap_or_else(|| DEFAULT_FILTER.to_string())];
+        if let Some(level) = self.contracts_log {
+            directives.push(format!("{CONTRACTS_TARGET}={level}"));
+        }
+        if let Some(level) = self.evm_log {
+            directives.push(format!("{EVM_TARGET}={level}"));
+        }
+        directives.join(",")
+    }
+
+    pub fn env_filter(&self) -> anyhow::Result<EnvFilter> {
+        Ok(EnvFilter::try_new(self.directives())?)
+    }
+
+    /// Install the global subscriber, writing to stderr.
+    pub fn init(&self) -> anyhow::Result<()> {
+        forward_log_records();
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(self.env_filter()?)
+            .with_writer(io::stderr)
+            .finish();
+        tracing::subscriber::set_global_default(subscriber)
+            .map_err(|err| anyhow::anyhow!("Failed to install subscriber: {err}"))
+    }
+}
+
+/// Install the global subscriber configured from `RUST_LOG`, writing to the test output.
+///
+/// Does nothing if a subscriber is already installed, so it can be called from every test.
+pub fn init_for_tests() {
+    forward_log_records();
+    if let Ok(filter) = LogArgs::from_env().env_filter() {
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_test_writer()
+            .finish();
+        tracing::subscriber::set_global_default(subscriber).ok();
+    }
+}
+
+/// Forward `log` records of all levels, as emitted by the runtime, to `tracing`.
+///
+/// The subscribers are installed without their own forwarding, which would lower the maximum level
+/// of the `log` crate to the level of their filter, and hide the records of more verbose scoped
+/// subscribers.
+fn forward_log_records() {
+    tracing_log::LogTracer::builder()
+        .with_max_level(log::LevelFilter::Trace)
+        .init()
+        .ok();
+}
+
+/// Execute `f` while capturing all logs matching the `filter` directives emitted on the current
+/// thread, e.g. to include them in a failure report.
+pub fn capture<T>(filter: &str, f: impl FnOnce() -> T) -> anyhow::Result<(T, String)> {
+    let (result, logs) = scoped(Some(EnvFilter::try_new(filter)?), None::<Targets>, f);
+    Ok((result, logs.unwrap_or_default()))
+}
+
+/// Execute `f` with a subscriber for the current thread, which captures the logs matching
+/// `capture` and enables what the `enable` filter accepts without recording it, e.g. a
+/// [`crate::strace::HostFnClock`] to switch on host function tracing.
+///
+/// Both are combined into one subscriber, since a nested scoped subscriber replaces the outer one
+/// instead of adding to it. Returns the captured logs if `capture` is set.
+pub fn scoped<T, E>(
+    capture: Option<EnvFilter>,
+    enable: Option<E>,
+    f: impl FnOnce() -> T,
+) -> (T, Option<String>)
+where
+    E: Filter<Registry> + Send + Sync + 'static,
+{
+    forward_log_records();
+
+    let buffer = capture.as_ref().map(|_| CaptureWriter::default());
+    let capture_layer = capture.zip(buffer.clone()).map(|(filter, buffer)| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(buffer)
+            .with_filter(filter)
+    });
+    let enable_layer = enable.map(|filter| Identity::new().with_filter(filter));
+    let subscriber = tracing_subscriber::registry()
+        .with(enable_layer)
+        .with(capture_layer);
+    let result = tracing::subscriber::with_default(subscriber, f);
+
+    let logs = buffer.map(|buffer| {
+        let logs = buffer.0.lock().expect("poisoned log buffer");
+        String::from_utf8_lossy(&logs).into_owned()
+    });
+    (result, logs)
+}
+
+#[derive(Clone, Default)]
+struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("poisoned log buffer").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CaptureWriter {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_levels_override_filter() {
+        let args = LogArgs {
+            filter: Some("info".to_string()),
+            ..Default::default()
+        }
+        .with_contracts_log(tracing::Level::DEBUG)
+        .with_evm_log(tracing::Level::TRACE);
+        assert_eq!(args.directives(), "info,runtime::contracts=DEBUG,evm=TRACE");
+        args.env_filter().unwrap();
+    }
+
+    #[test]
+    fn captures_matching_logs() {
+        let ((), logs) = capture("runtime::contracts=debug", || {
+            log::debug!(target: "runtime::contracts", "captured");
+            log::debug!(target: "evm", "filtered");
+        })
+        .unwrap();
+        assert!(logs.contains("captured"));
+        assert!(!logs.contains("filtered"));
+    }
+
+    #[test]
+    fn captures_logs_while_enabling_targets() {
+        let capture = EnvFilter::try_new("runtime::contracts=debug").unwrap();
+        let enable =
+            Targets::new().with_target("runtime::contracts::strace", tracing::Level::TRACE);
+        let (enabled, logs) = scoped(Some(capture), Some(enable), || {
+            log::debug!(target: "runtime::contracts", "captured");
+            log::trace!(target: "runtime::contracts::strace", "only enabled");
+            log::log_enabled!(target: "runtime::contracts::strace", log::Level::Trace)
+        });
+        assert!(enabled);
+        let logs = logs.unwrap();
+        assert!(logs.contains("captured"));
+        assert!(!logs.contains("only enabled"));
+    }
+
+    #[test]
+    fn forwards_all_levels_without_changing_them_per_scope() {
+        init_for_tests();
+        assert_eq!(log::max_level(), log::LevelFilter::Trace);
+        let ((), _) = capture("runtime::contracts=debug", || {
+            assert_eq!(log::max_level(), log::LevelFilter::Trace);
+        })
+        .unwrap();
+        assert_eq!(log::max_level(), log::LevelFilter::Trace);
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use schlau::{build::BuildAll, logging::LogArgs};
+
+#[derive(Parser)]
+#[command(name = "schlau", about = "Smart contract benchmarks")]
+struct Cli {
+    #[command(flatten)]
+    log: LogArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build all benchmark contracts for the enabled targets, before running the benchmarks.
+    BuildAll {
+        /// The directory containing the `ink` and `solidity` contracts.
+        #[arg(long, default_value = schlau::build::CONTRACTS_DIR)]
+        contracts_dir: PathBuf,
+        /// The number of contracts to build in parallel, defaults to the number of CPUs.
+        #[arg(long, short)]
+        jobs: Option<usize>,
+        /// Don't print the progress.
+        #[arg(long, short)]
+        quiet: bool,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.log.init()?;
+
+    match cli.command {
+        Command::BuildAll {
+            contracts_dir,
+            jobs,
+            quiet,
+        } => {
+            let mut build_all = BuildAll::new()
+                .with_contracts_dir(contracts_dir)
+                .with_progress(!quiet);
+            if let Some(jobs) = jobs {
+                build_all = build_all.with_jobs(jobs);
+            }
+            let built = build_all.run()?;
+            if !quiet {
+                eprintln!("Built {} contract(s)", built.len());
+            }
+        }
+    }
+    Ok(())
+}
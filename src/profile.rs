@@ -0,0 +1,404 @@
+//! Instruction profiles of contract code blobs.
+//!
+//! Counts the VM instructions of each function in a contract, symbolized using the debug
+//! information in the code blob: the `name` section for Wasm and the debug line programs for
+//! PolkaVM. This allows comparing the output of different compilers, e.g. Solang and ink!, for
+//! the same logic.
+//!
+//! [`CodeProfile::from_code`] counts statically, i.e. the number of instructions in the code of
+//! each function. The number of instructions executed by a call is counted by
+//! [`InstrumentedCode`], which instruments a Wasm code blob to report every executed block through
+//! a chain extension of the [`crate::contracts::ContractsRuntime`]. Counting executed RISC-V
+//! instructions is not supported, since `pallet-contracts` does not expose the PolkaVM instance,
+//! so [`InstrumentedCode::instrument`] rejects PolkaVM blobs. Only static counts are available for
+//! them.
+//!
+//! Contracts should be built with debug symbols (see
+//! [`crate::ink::build_contract_with_debug_symbols`] and
+//! [`crate::solang::build_contract_with_debug_symbols`]), otherwise functions are only identified
+//! by their index.
+
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use contract_build::Target;
+use wasm_instrument::{
+    gas_metering::{self, host_function, ConstantCostRules},
+    parity_wasm::{
+        self,
+        elements::{
+            External, FunctionType, ImportCountType, Instruction, Module, Section, Type, ValueType,
+        },
+    },
+};
+
+use crate::contracts::chain_extension::{register_chain_extension, unregister_chain_extension};
+
+/// The first chain extension id used by [`InstrumentedCode`]. Extension ids from this one on are
+/// reserved for counting executed blocks.
+pub const PROFILE_EXTENSION_ID: u16 = 0xff00;
+
+/// `pallet-contracts` skips writing the output of a host function to this output pointer.
+const SENTINEL: u32 = u32::MAX;
+
+/// The magic bytes at the start of a PolkaVM program blob.
+const POLKAVM_MAGIC: &[u8] = b"PVM\0";
+
+/// The instruction counts of the functions in a contract code blob, or executed by a call.
+#[derive(Debug, Clone)]
+pub struct CodeProfile {
+    pub target: Target,
+    /// The number of instructions of each function, keyed by function name.
+    pub functions: BTreeMap<String, u64>,
+}
+
+impl CodeProfile {
+    /// Profile the given code blob for `target`.
+    pub fn from_code(code: &[u8], target: Target) -> anyhow::Result<Self> {
+        let functions = match target {
+            Target::Wasm => wasm_functions(code)?,
+            Target::RiscV => riscv_functions(code)?,
+        };
+        Ok(Self { target, functions })
+    }
+
+    /// The total number of instructions in the code blob.
+    pub fn total_instructions(&self) -> u64 {
+        self.functions.values().sum()
+    }
+
+    /// The functions ordered by instruction count, largest first.
+    pub fn functions_by_size(&self) -> Vec<(&str, u64)> {
+        let mut functions = self
+            .functions
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect::<Vec<_>>();
+        functions.sort_by(|(_, a), (_, b)| b.cmp(a));
+        functions
+    }
+}
+
+/// A Wasm code blob instrumented to count the instructions it executes.
+///
+/// The code is split into blocks of straight line code like for gas metering, and a call to
+/// `seal0::call_chain_extension` is injected at the start of each block, with the block encoded in
+/// the extension and function id. The chain extension adds the number of instructions in the block
+/// to the function containing it. The injected calls themselves are not counted.
+///
+/// The instrumented code only runs on the [`crate::contracts::ContractsRuntime`], and only within
+/// [`InstrumentedCode::record`], including its instantiation. It is slower and consumes more
+/// weight than the original code, so it must not be used for benchmarking.
+#[derive(Debug, Clone)]
+pub struct InstrumentedCode {
+    /// The instrumented code blob, to be uploaded instead of the original.
+    pub code: Vec<u8>,
+    /// The function name and instruction count of each block.
+    blocks: Vec<(String, u64)>,
+}
+
+impl InstrumentedCode {
+    /// Instrument the given Wasm code blob.
+    pub fn instrument(code: &[u8]) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !code.starts_with(POLKAVM_MAGIC),
+            "Executed instructions can only be counted for Wasm, use CodeProfile::from_code to \
+             count the instructions of a PolkaVM blob"
+        );
+        let to_err = |err: parity_wasm::elements::Error| anyhow::anyhow!("Invalid Wasm: {err}");
+        let module = parity_wasm::deserialize_buffer::<Module>(code).map_err(to_err)?;
+        let mut module = module.parse_names().unwrap_or_else(|(_, module)| module);
+
+        let imported_functions = module.import_count(ImportCountType::Function) as u32;
+        let function_names = module
+            .names_section()
+            .and_then(|names| names.functions())
+            .map(|functions| functions.names().clone());
+        let function_name = |nth_function: usize| {
+            let index = imported_functions + nth_function as u32;
+            function_names
+                .as_ref()
+                .and_then(|names| names.get(index).cloned())
+                .unwrap_or_else(|| format!("func[{index}]"))
+        };
+        // the names are resolved above, and the indices are shifted by the injected import
+        module
+            .sections_mut()
+            .retain(|section| !matches!(section, Section::Name(_) | Section::Custom(_)));
+
+        // meter each block with a call to an imported `gas(i64)` function, named like the chain
+        // extension host function
+        let backend = host_function::Injector::new("seal0", "call_chain_extension");
+        let mut module = gas_metering::inject(module, backend, &ConstantCostRules::new(1, 0, 0))
+            .map_err(|_| anyhow::anyhow!("Failed to instrument the Wasm code"))?;
+        let counter = imported_functions;
+
+        // fix up the signature of the injected import
+        let types = module
+            .type_section_mut()
+            .ok_or_else(|| anyhow::anyhow!("No type section"))?
+            .types_mut();
+        let chain_extension_type = Type::Function(FunctionType::new(
+            vec![ValueType::I32; 5],
+            vec![ValueType::I32],
+        ));
+        let type_index = match types.iter().position(|ty| *ty == chain_extension_type) {
+            Some(index) => index,
+            None => {
+                types.push(chain_extension_type);
+                types.len() - 1
+            }
+        } as u32;
+        let import = module
+            .import_section_mut()
+            .and_then(|imports| {
+                imports
+                    .entries_mut()
+                    .iter_mut()
+                    .filter(|entry| matches!(entry.external(), External::Function(_)))
+                    .nth(counter as usize)
+            })
+            .ok_or_else(|| anyhow::anyhow!("No injected import"))?;
+        *import.external_mut() = External::Function(type_index);
+
+        // replace the gas charges with chain extension calls identifying the block
+        let mut blocks = Vec::new();
+        let bodies = module
+            .code_section_mut()
+            .map(|code| code.bodies_mut().as_mut_slice())
+            .unwrap_or_default();
+        for (nth_function, body) in bodies.iter_mut().enumerate() {
+            let instructions = body.code_mut().elements_mut();
+            let mut instrumented = Vec::with_capacity(instructions.len());
+            for instruction in instructions.drain(..) {
+                if instruction == Instruction::Call(counter) {
+                    let Some(Instruction::I64Const(cost)) = instrumented.pop() else {
+                        anyhow::bail!("Unexpected gas metering instructions");
+                    };
+                    let id = block_id(blocks.len())?;
+                    blocks.push((function_name(nth_function), cost as u64));
+                    instrumented.extend([
+                        Instruction::I32Const(id as i32),
+                        // input_ptr, input_len
+                        Instruction::I32Const(0),
+                        Instruction::I32Const(0),
+                        // output_ptr, output_len_ptr
+                        Instruction::I32Const(SENTINEL as i32),
+                        Instruction::I32Const(0),
+                        Instruction::Call(counter),
+                        Instruction::Drop,
+                    ]);
+                } else {
+                    instrumented.push(instruction);
+                }
+            }
+            *instructions = instrumented;
+        }
+
+        let code = parity_wasm::serialize(module).map_err(to_err)?;
+        Ok(Self { code, blocks })
+    }
+
+    /// Execute `f`, counting the instructions executed by the instrumented code.
+    ///
+    /// The chain extensions counting the blocks are registered on the current thread for the
+    /// duration of `f`, and unregistered again even if `f` panics.
+    pub fn record<T>(&self, f: impl FnOnce() -> T) -> (T, CodeProfile) {
+        let executions = Rc::new(RefCell::new(vec![0u64; self.blocks.len()]));
+        let counters = BlockCounters(self.blocks.len());
+        for nth_block in 0..self.blocks.len() {
+            let (extension_id, func_id) = split_block_id(nth_block);
+            let executions = executions.clone();
+            register_chain_extension(extension_id, func_id, move |_| {
+                executions.borrow_mut()[nth_block] += 1;
+                Ok(Vec::new())
+            });
+        }
+
+        let result = f();
+        drop(counters);
+
+        let mut functions = BTreeMap::<String, u64>::new();
+        for ((function, instructions), count) in self.blocks.iter().zip(executions.borrow().iter())
+        {
+            if *count > 0 {
+                *functions.entry(function.clone()).or_default() += instructions * count;
+            }
+        }
+        let profile = CodeProfile {
+            target: Target::Wasm,
+            functions,
+        };
+        (result, profile)
+    }
+}
+
+/// Unregisters the chain extensions counting the first `.0` blocks when dropped.
+struct BlockCounters(usize);
+
+impl Drop for BlockCounters {
+    fn drop(&mut self) {
+        for nth_block in 0..self.0 {
+            let (extension_id, func_id) = split_block_id(nth_block);
+            unregister_chain_extension(extension_id, func_id);
+        }
+    }
+}
+
+/// The chain extension and function id of the `nth_block`.
+fn split_block_id(nth_block: usize) -> (u16, u16) {
+    (
+        PROFILE_EXTENSION_ID + (nth_block >> 16) as u16,
+        nth_block as u16,
+    )
+}
+
+/// The id passed to `call_chain_extension` for the `nth_block`.
+fn block_id(nth_block: usize) -> anyhow::Result<u32> {
+    let max_blocks = (u16::MAX - PROFILE_EXTENSION_ID + 1) as usize * (1 << 16);
+    anyhow::ensure!(
+        nth_block < max_blocks,
+        "Too many blocks to instrument, at most {max_blocks} are supported"
+    );
+    let (extension_id, func_id) = split_block_id(nth_block);
+    Ok((u32::from(extension_id) << 16) | u32::from(func_id))
+}
+
+fn wasm_functions(code: &[u8]) -> anyhow::Result<BTreeMap<String, u64>> {
+    use wasmparser::{Name, NameSectionReader, Parser, Payload, TypeRef};
+
+    let mut imported_functions = 0;
+    let mut counts = Vec::new();
+    let mut names = BTreeMap::new();
+
+    for payload in Parser::new(0).parse_all(code) {
+        match payload? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    if let TypeRef::Func(_) = import?.ty {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut count = 0u64;
+                let mut operators = body.get_operators_reader()?;
+                while !operators.eof() {
+                    operators.read()?;
+                    count += 1;
+                }
+                counts.push(count);
+            }
+            Payload::CustomSection(section) if section.name() == "name" => {
+                let reader = NameSectionReader::new(section.data(), section.data_offset());
+                for name in reader {
+                    if let Name::Function(functions) = name? {
+                        for naming in functions {
+                            let naming = naming?;
+                            names.insert(naming.index, naming.name.to_owned());
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let index = imported_functions + i as u32;
+            let name = names
+                .remove(&index)
+                .unwrap_or_else(|| format!("func[{index}]"));
+            (name, count)
+        })
+        .collect())
+}
+
+fn riscv_functions(code: &[u8]) -> anyhow::Result<BTreeMap<String, u64>> {
+    use polkavm_common::program::ProgramBlob;
+
+    let blob = ProgramBlob::parse(code).map_err(|err| anyhow::anyhow!("{err}"))?;
+    let mut instruction_count = 0;
+    for instruction in blob.instructions() {
+        instruction.map_err(|err| anyhow::anyhow!("{err}"))?;
+        instruction_count += 1;
+    }
+    let mut functions = BTreeMap::<String, u64>::new();
+    // the function covering the most recently symbolized range of instructions
+    let mut current: Option<(std::ops::Range<u32>, String)> = None;
+
+    for nth_instruction in 0..instruction_count {
+        let in_current = matches!(&current, Some((range, _)) if range.contains(&nth_instruction));
+        if !in_current {
+            current = symbolize_riscv(&blob, nth_instruction)?;
+        }
+        let name = match &current {
+            Some((_, name)) => name.clone(),
+            None => "<unknown>".to_owned(),
+        };
+        *functions.entry(name).or_default() += 1;
+    }
+    Ok(functions)
+}
+
+/// Find the name of the outermost function containing the given instruction, along with the
+/// range of instructions the name applies to.
+fn symbolize_riscv(
+    blob: &polkavm_common::program::ProgramBlob,
+    nth_instruction: u32,
+) -> anyhow::Result<Option<(std::ops::Range<u32>, String)>> {
+    let to_err = |err| anyhow::anyhow!("Invalid debug info: {err}");
+
+    let Some(mut line_program) = blob
+        .get_debug_line_program_at(nth_instruction)
+        .map_err(to_err)?
+    else {
+        return Ok(None);
+    };
+    while let Some(region) = line_program.run().map_err(to_err)? {
+        let range = region.instruction_range();
+        if !range.contains(&nth_instruction) {
+            continue;
+        }
+        let Some(frame) = region.frames().next() else {
+            return Ok(None);
+        };
+        let name = frame.full_name().map_err(to_err)?.to_string();
+        return Ok(Some((range, name)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::chain_extension::chain_extension;
+
+    #[test]
+    fn rejects_polkavm_blobs() {
+        let err = InstrumentedCode::instrument(b"PVM\0\x01").unwrap_err();
+        assert!(
+            err.to_string().contains("only be counted for Wasm"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn unregisters_block_counters_on_panic() {
+        let instrumented = InstrumentedCode {
+            code: Vec::new(),
+            blocks: vec![("f".to_owned(), 1)],
+        };
+        let (extension_id, func_id) = split_block_id(0);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            instrumented.record(|| {
+                assert!(chain_extension(extension_id, func_id).is_some());
+                panic!("call failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert!(chain_extension(extension_id, func_id).is_none());
+    }
+}
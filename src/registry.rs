@@ -0,0 +1,35 @@
+//! Thread-local registries of test doubles implemented in Rust, e.g. custom precompiles and chain
+//! extensions.
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Entries registered by key, to be declared in a `thread_local!`.
+pub(crate) struct Registry<K, V> {
+    entries: RefCell<BTreeMap<K, V>>,
+}
+
+impl<K: Ord, V: Clone> Registry<K, V> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Register `value` under `key`, replacing any existing entry.
+    pub(crate) fn insert(&self, key: K, value: V) {
+        self.entries.borrow_mut().insert(key, value);
+    }
+
+    /// Remove the entry under `key`, if any.
+    pub(crate) fn remove(&self, key: &K) {
+        self.entries.borrow_mut().remove(key);
+    }
+
+    /// A clone of the entry under `key`.
+    ///
+    /// The registry is not borrowed while the entry is used, so e.g. a registered closure may
+    /// register or remove entries itself.
+    pub(crate) fn get(&self, key: &K) -> Option<V> {
+        self.entries.borrow().get(key).cloned()
+    }
+}
@@ -1,34 +1,48 @@
 use std::{
-    path::{Path, PathBuf},
-    process::Command,
+    ffi::OsString,
+    path::{Component, Path, PathBuf},
+    process::{Command, Output},
 };
 
 use crate::{
-    drink::runtime::{AccountIdFor, MinimalRuntime},
-    drink::Weight,
-    drink_api::{CallArgs, CreateArgs, DrinkApi},
+    diagnostics::{CompileError, Diagnostic},
+    drink::runtime::{AccountIdFor, MinimalRuntime, Runtime as RuntimeT},
+    drink::{pallet_balances, pallet_contracts, BalanceOf, Weight},
+    drink_api::{CallArgs, ContractsBalanceOf, CreateArgs, DrinkApi},
+    solidity_imports::SolidityImports,
+    toolchain::{self, Tool},
 };
 use contract_build::Target;
 use parity_scale_codec::Encode;
 use subxt_signer::sr25519::dev;
 
-pub struct SolangContract {
-    pub drink_api: DrinkApi<MinimalRuntime>,
-    contract_account: AccountIdFor<MinimalRuntime>,
+pub struct SolangContract<Runtime: RuntimeT = MinimalRuntime> {
+    pub drink_api: DrinkApi<Runtime>,
+    contract_account: AccountIdFor<Runtime>,
     build_result: BuildResult,
 }
 
 impl SolangContract {
     pub fn init(name: &str) -> Self {
+        Self::init_with_api(name, DrinkApi::<MinimalRuntime>::new())
+    }
+}
+
+impl<Runtime> SolangContract<Runtime>
+where
+    Runtime: RuntimeT + pallet_balances::Config + pallet_contracts::Config,
+    AccountIdFor<Runtime>: From<[u8; 32]> + AsRef<[u8; 32]>,
+    BalanceOf<Runtime>: From<u128>,
+    ContractsBalanceOf<Runtime>: From<u128>,
+{
+    /// Build the contract `name` and instantiate it in the sandbox of `drink_api`.
+    pub fn init_with_api(name: &str, mut drink_api: DrinkApi<Runtime>) -> Self {
         let build_result =
             build_and_load_contract(&format!("contracts/solidity/{}.sol", name)).unwrap();
 
-        let mut drink_api = DrinkApi::<MinimalRuntime>::new();
-
         let constructor_selector = build_result.constructor_selector("new").unwrap();
-        let create_args =
-            CreateArgs::<MinimalRuntime>::new(build_result.code.clone(), dev::alice())
-                .with_data(constructor_selector);
+        let create_args = CreateArgs::<Runtime>::new(build_result.code.clone(), dev::alice())
+            .with_data(constructor_selector);
 
         let contract_account = drink_api.instantiate_with_code(create_args).unwrap();
 
@@ -39,60 +53,226 @@ impl SolangContract {
         }
     }
 
-    pub fn call_args<Args: Encode>(&self, message: &str, args: Args) -> CallArgs<MinimalRuntime> {
+    /// The account the contract is instantiated at.
+    pub fn contract_account(&self) -> AccountIdFor<Runtime> {
+        self.contract_account.clone()
+    }
+
+    pub fn call_args<Args: Encode>(&self, message: &str, args: Args) -> CallArgs<Runtime> {
+        self.call_args_encoded(message, args.encode())
+    }
+
+    /// Create the call args for `message` from the already SCALE encoded `args`.
+    pub fn call_args_encoded(&self, message: &str, mut args: Vec<u8>) -> CallArgs<Runtime> {
         let mut call_data = self.build_result.message_selector(message).unwrap();
-        call_data.append(&mut args.encode());
+        call_data.append(&mut args);
 
-        CallArgs::<MinimalRuntime>::new(self.contract_account.clone(), dev::alice(), call_data)
+        CallArgs::<Runtime>::new(self.contract_account.clone(), dev::alice(), call_data)
             .with_gas_limit(Weight::MAX)
     }
 }
 
 /// Builds the Solidity source in `path_to_source_sol`.
-/// Returns the path to the build output directory.
+/// Returns the path to the build output directory, see [`out_dir`].
 ///
 /// For each each contract found in the source code, solang creates two files:
-/// - `contract_name.wasm`: The code blob.
+/// - `contract_name.wasm` or `contract_name.polkavm`: The code blob, see [`code_extension`].
 /// - `contract_name.contract`: The full contract artifact including metadata.
 /// Where `contract_name` is equal to the name of that contract
 ///
-/// Note: For RiscV the produced contract blob does still have the `.wasm` file extension.
+/// On failure the error is a [`CompileError`] with the diagnostics reported by solang.
 pub fn build_contract<P>(path_to_source_sol: P, target: Target) -> anyhow::Result<PathBuf>
 where
     P: AsRef<Path> + Copy,
 {
-    let target = match target {
-        Target::RiscV => "polkadot-riscv",
-        Target::Wasm => "polkadot",
-    };
+    build_contract_with_imports(path_to_source_sol, target, &SolidityImports::load()?)
+}
+
+/// Builds the Solidity source in `path_to_source_sol`, resolving its imports with `imports`.
+/// Returns the path to the build output directory.
+pub fn build_contract_with_imports<P>(
+    path_to_source_sol: P,
+    target: Target,
+    imports: &SolidityImports,
+) -> anyhow::Result<PathBuf>
+where
+    P: AsRef<Path> + Copy,
+{
+    compile(path_to_source_sol, target, true, imports)
+}
 
-    let bin_path = PathBuf::from("bin").join("solang");
+/// Builds the Solidity source in `path_to_source_sol` with debug information in the code blob,
+/// for use with [`crate::profile::CodeProfile`].
+/// Returns the path to the build output directory.
+///
+/// The contract is compiled in debug mode with `--generate-debug-information`, without running
+/// `wasm-opt`, so the code differs from the optimized build produced by [`build_contract`].
+pub fn build_contract_with_debug_symbols<P>(
+    path_to_source_sol: P,
+    target: Target,
+) -> anyhow::Result<PathBuf>
+where
+    P: AsRef<Path> + Copy,
+{
+    compile(path_to_source_sol, target, false, &SolidityImports::load()?)
+}
 
-    let out_dir = path_to_source_sol
-        .as_ref()
-        .parent()
-        .expect("source file is not in root dir");
+fn compile<P>(
+    path_to_source_sol: P,
+    target: Target,
+    release: bool,
+    imports: &SolidityImports,
+) -> anyhow::Result<PathBuf>
+where
+    P: AsRef<Path> + Copy,
+{
+    let out_dir = out_dir(path_to_source_sol, target, release, imports);
+    std::fs::create_dir_all(&out_dir)?;
+
+    let bin_path = toolchain::resolve(Tool::Solang)?;
 
     match Command::new(&bin_path)
-        .arg("compile")
-        .arg("--target")
-        .arg(target)
-        .arg("-O")
-        .arg("aggressive")
-        .arg("--release")
-        .arg("--wasm-opt")
-        .arg("z")
+        .args(compile_args(target, release, imports))
         .arg("-o")
-        .arg(out_dir)
+        .arg(&out_dir)
         .arg(path_to_source_sol.as_ref())
         .output()
     {
-        Ok(output) if output.status.success() => Ok(out_dir.to_path_buf()),
-        Ok(output) => Err(anyhow::anyhow!("Failed to compile contract:\n {output:?}")),
+        Ok(output) if output.status.success() => {
+            if target == Target::RiscV {
+                rename_riscv_blobs(&out_dir)?;
+            }
+            Ok(out_dir)
+        }
+        Ok(output) => {
+            let source = path_to_source_sol.as_ref();
+            Err(diagnose(&bin_path, target, source, imports, &output).into())
+        }
         Err(msg) => Err(anyhow::anyhow!("Failed to execute {bin_path:?}: {msg:?}")),
     }
 }
 
+/// The `solang` target name for `target`.
+fn solang_target(target: Target) -> &'static str {
+    match target {
+        Target::RiscV => "polkadot-riscv",
+        Target::Wasm => "polkadot",
+    }
+}
+
+/// The arguments of `solang compile`, without the output directory and the source.
+fn compile_args(target: Target, release: bool, imports: &SolidityImports) -> Vec<OsString> {
+    let mut args = vec![
+        OsString::from("compile"),
+        "--target".into(),
+        solang_target(target).into(),
+        "-O".into(),
+        "aggressive".into(),
+    ];
+    args.extend(imports.solang_args());
+    if release {
+        args.extend(["--release", "--wasm-opt", "z"].map(Into::into));
+    } else {
+        args.push("--generate-debug-information".into());
+    }
+    args
+}
+
+/// The file extension of the code blobs built for `target`.
+///
+/// `solang` names the code blob `.wasm` for all targets, RISC-V blobs are renamed to `.polkavm`.
+pub fn code_extension(target: Target) -> &'static str {
+    match target {
+        Target::RiscV => "polkavm",
+        Target::Wasm => "wasm",
+    }
+}
+
+/// Give the RISC-V code blobs in `out_dir` their proper extension.
+fn rename_riscv_blobs(out_dir: &Path) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(out_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "wasm") {
+            std::fs::rename(&path, path.with_extension(code_extension(Target::RiscV)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Collect the diagnostics of a failed compilation by compiling again with `--standard-json`,
+/// which conflicts with writing the artifacts. Falls back to the output of the failed compilation
+/// if the diagnostics can't be parsed.
+fn diagnose(
+    bin_path: &Path,
+    target: Target,
+    source: &Path,
+    imports: &SolidityImports,
+    failed: &Output,
+) -> CompileError {
+    let diagnostics = Command::new(bin_path)
+        .arg("compile")
+        .arg("--target")
+        .arg(solang_target(target))
+        .args(imports.solang_args())
+        .arg("--standard-json")
+        .arg(source)
+        .output()
+        .ok()
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok())
+        .and_then(|json| Diagnostic::from_standard_json(&json, &imports.search_paths()).ok())
+        .unwrap_or_default();
+
+    CompileError {
+        compiler: Tool::Solang,
+        source: source.to_path_buf(),
+        diagnostics,
+        output: String::from_utf8_lossy(&failed.stderr).into_owned(),
+    }
+}
+
+/// The build output directory for the Solidity source `path_to_source_sol`, i.e.
+/// `target/solang/<target>/<release|debug>-<options hash>/<source path without extension>`.
+///
+/// Each target, build mode and set of compiler options has its own directory, so concurrent builds
+/// don't overwrite each other's artifacts, and the source tree stays clean. The source path is
+/// relative to the working directory, so sources with the same name in different directories
+/// don't collide either.
+pub fn out_dir<P: AsRef<Path>>(
+    path_to_source_sol: P,
+    target: Target,
+    release: bool,
+    imports: &SolidityImports,
+) -> PathBuf {
+    let target_name = match target {
+        Target::RiscV => "riscv",
+        Target::Wasm => "wasm",
+    };
+    let profile = if release { "release" } else { "debug" };
+    let options = compile_args(target, release, imports)
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let options_hash = hex::encode(sp_core::hashing::twox_64(options.as_bytes()));
+
+    let source = path_to_source_sol.as_ref().with_extension("");
+    let source = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| source.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or(source);
+    // only keep the normal components, so the directory stays below the output directory
+    let source = source
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect::<PathBuf>();
+
+    crate::target_dir()
+        .join("solang")
+        .join(target_name)
+        .join(format!("{profile}-{options_hash}"))
+        .join(source)
+}
+
 pub fn build_and_load_contract<P>(path_to_source_sol: P) -> anyhow::Result<BuildResult>
 where
     P: AsRef<Path> + Copy,
@@ -162,18 +342,43 @@ impl BuildResult {
 mod tests {
     use std::{fs, path::PathBuf};
 
+    use crate::{profile::CodeProfile, solidity_imports::SolidityImports};
+
     fn can_compile(target: contract_build::Target) {
-        let path = PathBuf::from("contracts/solidity/compile_test");
+        let path = PathBuf::from("contracts/solidity/compile_test.sol");
 
-        super::build_contract(&path.with_extension("sol"), target).unwrap();
+        let imports = SolidityImports::load().unwrap();
+        let out_dir = super::build_contract(&path, target).unwrap();
+        assert_eq!(out_dir, super::out_dir(&path, target, true, &imports));
+        assert!(out_dir.ends_with("contracts/solidity/compile_test"));
+        assert_ne!(out_dir, super::out_dir(&path, target, false, &imports));
 
-        let len = fs::read(path.with_extension("wasm"))
+        let blob = format!("compile_test.{}", super::code_extension(target));
+        let len = fs::read(out_dir.join(blob))
             .expect("compiler should produce a contract blob")
             .len();
         assert!(len > 0, "compiler should produce a non-empty contract blob");
+        assert!(
+            !path.with_extension("wasm").exists(),
+            "compiler should not write next to the source"
+        );
+    }
 
-        let _ = std::fs::remove_file(path.with_extension("wasm"));
-        let _ = std::fs::remove_file(path.with_extension("contract"));
+    fn has_debug_symbols(target: contract_build::Target) {
+        let path = PathBuf::from("contracts/solidity/compile_test.sol");
+
+        let out_dir = super::build_contract_with_debug_symbols(&path, target).unwrap();
+        let blob = format!("compile_test.{}", super::code_extension(target));
+        let code = fs::read(out_dir.join(blob)).unwrap();
+        let profile = CodeProfile::from_code(&code, target).unwrap();
+        assert!(
+            profile
+                .functions
+                .keys()
+                .any(|name| !name.starts_with("func[") && name != "<unknown>"),
+            "functions should be symbolized, found {:?}",
+            profile.functions.keys()
+        );
     }
 
     #[cfg(feature = "wasm")]
@@ -182,9 +387,21 @@ mod tests {
         can_compile(contract_build::Target::Wasm)
     }
 
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn wasm_debug_symbols() {
+        has_debug_symbols(contract_build::Target::Wasm)
+    }
+
     #[cfg(feature = "riscv")]
     #[test]
     fn can_compile_riscv() {
         can_compile(contract_build::Target::RiscV)
     }
+
+    #[cfg(feature = "riscv")]
+    #[test]
+    fn riscv_debug_symbols() {
+        has_debug_symbols(contract_build::Target::RiscV)
+    }
 }
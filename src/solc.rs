@@ -1,56 +1,118 @@
+use crate::{
+    diagnostics::{CompileError, Diagnostic, Severity},
+    solidity_imports::SolidityImports,
+    toolchain::{self, Tool},
+};
 use alloy_json_abi::JsonAbi;
+use serde_json::{json, Value};
 use std::{
-    path::{Path, PathBuf},
-    process::Command,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
 };
 
 /// Builds the Solidity source in `path_to_source_sol`.
 /// Returns the bytes of the compiled contract.
+///
+/// The source is compiled via the standard JSON interface of `solc`. If it contains more than
+/// one contract, the one named like the source file is selected.
+///
+/// On failure the error is a [`CompileError`] with the diagnostics reported by `solc`.
 pub fn build_contract<P>(path_to_source_sol: P) -> anyhow::Result<BuildResult>
 where
     P: AsRef<Path> + Copy,
 {
-    let bin_path = PathBuf::from("bin").join("solc");
-
-    let abi_str =
-        run_and_extract_output(path_to_source_sol, &bin_path, "--abi", "Contract JSON ABI")?;
-    let abi: JsonAbi = serde_json::from_str(&abi_str)?;
-
-    let code_hex = run_and_extract_output(path_to_source_sol, &bin_path, "--bin", "Binary:")?;
-    let code = hex::decode(code_hex)?;
-
-    Ok(BuildResult { abi, code })
+    build_contract_with_imports(path_to_source_sol, &SolidityImports::load()?)
 }
 
-fn run_and_extract_output<P>(
+/// Builds the Solidity source in `path_to_source_sol`, resolving its imports with `imports`.
+pub fn build_contract_with_imports<P>(
     path_to_source_sol: P,
-    bin_path: &PathBuf,
-    arg: &str,
-    prefix: &str,
-) -> anyhow::Result<String>
+    imports: &SolidityImports,
+) -> anyhow::Result<BuildResult>
 where
     P: AsRef<Path> + Copy,
 {
-    let output = Command::new(bin_path)
-        .arg(arg)
-        .arg(path_to_source_sol.as_ref())
-        .output()?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8(output.stdout)?;
-        let mut prev_line = None;
-        for line in stdout.lines() {
-            if prev_line == Some(prefix) {
-                return Ok(line.trim_start().to_owned());
+    let bin_path = toolchain::resolve(Tool::Solc)?;
+    let source_path = path_to_source_sol.as_ref();
+    // the source unit name, relative imports in the source are resolved relative to it
+    let source_key = source_path
+        .strip_prefix(&imports.base_path)
+        .unwrap_or(source_path)
+        .to_string_lossy()
+        .into_owned();
+
+    let mut sources = serde_json::Map::new();
+    sources.insert(
+        source_key.clone(),
+        json!({ "content": std::fs::read_to_string(source_path)? }),
+    );
+    let input = json!({
+        "language": "Solidity",
+        "sources": sources,
+        "settings": {
+            "remappings": imports.solc_remappings(),
+            "outputSelection": {
+                "*": { "*": ["abi", "evm.bytecode.object"] }
             }
-            prev_line = Some(line);
         }
-        Err(anyhow::anyhow!("Failed to find '{prefix}' in output"))
-    } else {
-        Err(anyhow::anyhow!(
-            "Failed to execute command fod contract:\n {output:?}"
-        ))
+    });
+
+    let mut child = Command::new(&bin_path)
+        .arg("--standard-json")
+        .args(imports.solc_args())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| anyhow::anyhow!("Failed to execute {}: {err}", bin_path.display()))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.to_string().as_bytes())?;
+    let output = child.wait_with_output()?;
+
+    let compile_error = |diagnostics| CompileError {
+        compiler: Tool::Solc,
+        source: source_path.to_path_buf(),
+        diagnostics,
+        output: String::from_utf8_lossy(&output.stderr).into_owned(),
+    };
+
+    let Ok(json) = serde_json::from_slice::<Value>(&output.stdout) else {
+        return Err(compile_error(Vec::new()).into());
+    };
+    let diagnostics = Diagnostic::from_standard_json(&json, &imports.search_paths())?;
+    if !output.status.success()
+        || diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return Err(compile_error(diagnostics).into());
     }
+
+    let contracts = json["contracts"][&source_key]
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("No contracts in {source_key}"))?;
+    let contract_name = source_path.file_stem().and_then(|stem| stem.to_str());
+    let contract = match contract_name.and_then(|name| contracts.get(name)) {
+        Some(contract) => contract,
+        None if contracts.len() == 1 => contracts.values().next().expect("one contract"),
+        None => anyhow::bail!(
+            "Expected a contract named {} in {source_key}, found {:?}",
+            contract_name.unwrap_or_default(),
+            contracts.keys().collect::<Vec<_>>()
+        ),
+    };
+
+    let abi: JsonAbi = serde_json::from_value(contract["abi"].clone())?;
+    let code_hex = contract["evm"]["bytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("No bytecode in output for {source_key}"))?;
+    let code = hex::decode(code_hex)?;
+
+    Ok(BuildResult { abi, code })
 }
 
 #[derive(Debug)]
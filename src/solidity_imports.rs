@@ -0,0 +1,241 @@
+//! Import resolution for Solidity sources, shared by `solc` and `solang`.
+//!
+//! By default imports are resolved relative to the crate root as the base path, and to the
+//! vendored libraries in [`LIB_DIR`], e.g. `import "schlau/Math.sol";`. Remappings are loaded from
+//! [`REMAPPINGS_FILE`] if it exists, in the `prefix/=target/` format of `solc` and foundry.
+//!
+//! All default paths are resolved against the crate root, so imports resolve regardless of the
+//! working directory.
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::toolchain::crate_dir;
+
+/// The directory of vendored Solidity libraries, relative to the crate root.
+pub const LIB_DIR: &str = "contracts/solidity/lib";
+/// The file remappings are loaded from by [`SolidityImports::load`], relative to the crate root.
+pub const REMAPPINGS_FILE: &str = "contracts/solidity/remappings.txt";
+
+/// Maps imports starting with `prefix/` to `target/`.
+///
+/// The prefix is a whole path component, e.g. `@openzeppelin`, since `solang` only remaps the
+/// first component of an import path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Remapping {
+    pub prefix: String,
+    pub target: PathBuf,
+}
+
+impl Remapping {
+    /// Parse a remapping of the form `prefix/=target/`.
+    pub fn parse(remapping: &str) -> anyhow::Result<Self> {
+        let (prefix, target) = remapping.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("Invalid remapping {remapping}, expected prefix=target")
+        })?;
+        let prefix = prefix.trim().trim_end_matches('/');
+        let target = target.trim().trim_end_matches('/');
+        anyhow::ensure!(
+            !prefix.is_empty() && !prefix.contains('/') && !target.is_empty(),
+            "Invalid remapping {remapping}, the prefix must be a single path component"
+        );
+        Ok(Self {
+            prefix: prefix.to_string(),
+            target: PathBuf::from(target),
+        })
+    }
+}
+
+/// The base path, include paths and remappings to resolve imports with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolidityImports {
+    pub base_path: PathBuf,
+    pub include_paths: Vec<PathBuf>,
+    pub remappings: Vec<Remapping>,
+}
+
+impl Default for SolidityImports {
+    /// The crate root as base path and [`LIB_DIR`] as include path, without remappings.
+    fn default() -> Self {
+        Self {
+            base_path: crate_dir(),
+            include_paths: vec![crate_dir().join(LIB_DIR)],
+            remappings: Vec::new(),
+        }
+    }
+}
+
+impl SolidityImports {
+    /// The defaults, with the remappings of [`REMAPPINGS_FILE`] if it exists.
+    pub fn load() -> anyhow::Result<Self> {
+        let mut imports = Self::default();
+        let remappings_file = crate_dir().join(REMAPPINGS_FILE);
+        if remappings_file.is_file() {
+            imports = imports.with_remappings_file(remappings_file)?;
+        }
+        Ok(imports)
+    }
+
+    pub fn with_base_path<P: AsRef<Path>>(mut self, base_path: P) -> Self {
+        self.base_path = base_path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Also resolve imports relative to `include_path`.
+    pub fn with_include_path<P: AsRef<Path>>(mut self, include_path: P) -> Self {
+        self.include_paths.push(include_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Resolve imports starting with `prefix/` relative to `target`.
+    ///
+    /// A relative target is resolved against the base path, like the targets of
+    /// [`Self::with_remappings_file`], so the base path must be set first.
+    pub fn with_remapping<P: AsRef<Path>>(mut self, prefix: &str, target: P) -> Self {
+        self.remappings.push(Remapping {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            target: self.base_path.join(target),
+        });
+        self
+    }
+
+    /// Add the remappings in `path`, one per line, ignoring empty lines and `#` comments.
+    ///
+    /// Relative targets are resolved against the base path, like foundry resolves them against the
+    /// project root.
+    pub fn with_remappings_file<P: AsRef<Path>>(mut self, path: P) -> anyhow::Result<Self> {
+        let remappings = fs::read_to_string(path.as_ref())
+            .map_err(|err| anyhow::anyhow!("Failed to read {}: {err}", path.as_ref().display()))?;
+        for line in remappings.lines().map(str::trim) {
+            if !line.is_empty() && !line.starts_with('#') {
+                let mut remapping = Remapping::parse(line)?;
+                remapping.target = self.base_path.join(&remapping.target);
+                self.remappings.push(remapping);
+            }
+        }
+        Ok(self)
+    }
+
+    /// The directories source files and imports are resolved relative to, i.e. the base path
+    /// followed by the include paths.
+    pub fn search_paths(&self) -> Vec<PathBuf> {
+        std::iter::once(self.base_path.clone())
+            .chain(self.include_paths.iter().cloned())
+            .collect()
+    }
+
+    /// The command line arguments for `solc --standard-json`.
+    pub fn solc_args(&self) -> Vec<OsString> {
+        let mut args = vec!["--base-path".into(), self.base_path.clone().into()];
+        for include_path in &self.include_paths {
+            args.push("--include-path".into());
+            args.push(include_path.clone().into());
+        }
+        args
+    }
+
+    /// The remappings for the `settings` of the `solc` standard JSON input.
+    pub fn solc_remappings(&self) -> Vec<String> {
+        self.remappings
+            .iter()
+            .map(|remapping| format!("{}/={}/", remapping.prefix, remapping.target.display()))
+            .collect()
+    }
+
+    /// The command line arguments for `solang compile`.
+    ///
+    /// `solang` has no base path, so it is passed as the first import path.
+    pub fn solang_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        for import_path in self.search_paths() {
+            args.push("--importpath".into());
+            args.push(import_path.into());
+        }
+        for remapping in &self.remappings {
+            let mut map = OsString::from(format!("{}=", remapping.prefix));
+            map.push(&remapping.target);
+            args.push("--importmap".into());
+            args.push(map);
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_compiler_args() {
+        let imports = SolidityImports::default()
+            .with_base_path(".")
+            .with_remapping(
+                "@openzeppelin/",
+                "contracts/solidity/lib/openzeppelin-contracts",
+            );
+        let lib_dir = crate_dir().join(LIB_DIR);
+
+        assert_eq!(
+            imports.solc_args(),
+            [
+                OsString::from("--base-path"),
+                ".".into(),
+                "--include-path".into(),
+                lib_dir.clone().into()
+            ]
+        );
+        assert_eq!(
+            imports.solc_remappings(),
+            ["@openzeppelin/=./contracts/solidity/lib/openzeppelin-contracts/"]
+        );
+        assert_eq!(
+            imports.solang_args(),
+            [
+                OsString::from("--importpath"),
+                ".".into(),
+                "--importpath".into(),
+                lib_dir.into(),
+                "--importmap".into(),
+                "@openzeppelin=./contracts/solidity/lib/openzeppelin-contracts".into()
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_remapping_targets_against_base_path() {
+        let lib_dir = crate_dir().join(LIB_DIR);
+        let imports = SolidityImports::default()
+            .with_remapping("@schlau", "contracts/solidity/lib/schlau")
+            .with_remapping("@lib", &lib_dir);
+        assert_eq!(imports.remappings[0].target, lib_dir.join("schlau"));
+        assert_eq!(imports.remappings[1].target, lib_dir);
+    }
+
+    #[test]
+    fn resolves_defaults_against_crate_root() {
+        let imports = SolidityImports::load().unwrap();
+        assert_eq!(imports.base_path, crate_dir());
+        let schlau = imports
+            .remappings
+            .iter()
+            .find(|remapping| remapping.prefix == "@schlau")
+            .expect("remapping of the vendored library");
+        assert!(schlau.target.join("Math.sol").is_file());
+    }
+
+    #[test]
+    fn parses_remappings() {
+        assert_eq!(
+            Remapping::parse("@schlau/=contracts/solidity/lib/schlau/").unwrap(),
+            Remapping {
+                prefix: "@schlau".to_string(),
+                target: PathBuf::from("contracts/solidity/lib/schlau"),
+            }
+        );
+        assert!(Remapping::parse("no-target").is_err());
+        assert!(Remapping::parse("nested/prefix/=lib/").is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! Inspection of contract storage, see `DrinkApi::read_storage` and `EvmSandbox::read_storage`.
+//!
+//! [`InkStorageLayout`] decodes the root storage struct of an ink! contract using the storage
+//! layout and type registry of its metadata. Fields stored packed in the root storage item are
+//! decoded, including nested structs, enums and arrays, while lazily loaded fields only report the
+//! root key they are stored under.
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use parity_scale_codec::Encode;
+use scale_info::PortableRegistry;
+use serde_json::{Map, Value};
+
+/// All storage items of a contract, keyed by their raw storage key.
+///
+/// For `pallet-contracts` the keys are as stored in the child trie of the contract, i.e. hashed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageDump {
+    pub entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl StorageDump {
+    /// The number of storage items.
+    pub fn items(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The total size in bytes of all storage keys and values.
+    pub fn footprint(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum()
+    }
+}
+
+impl fmt::Display for StorageDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.entries {
+            writeln!(f, "0x{}: 0x{}", hex::encode(key), hex::encode(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// A decoded field of an ink! storage struct.
+#[derive(Clone, Debug)]
+pub enum StorageField {
+    /// A value stored packed in the root storage item.
+    Value(scale_value::Value<u32>),
+    /// A nested struct stored packed in the root storage item.
+    Struct(Vec<(String, StorageField)>),
+    /// A nested enum stored packed in the root storage item, with the fields of its variant.
+    Enum {
+        variant: String,
+        fields: Vec<(String, StorageField)>,
+    },
+    /// A nested array stored packed in the root storage item.
+    Array(Vec<StorageField>),
+    /// A lazily loaded value, e.g. `Lazy` or `Mapping`, stored under its own root key.
+    Lazy { root_key: u32 },
+}
+
+/// The root storage layout of an ink! contract, loaded from its metadata.
+pub struct InkStorageLayout {
+    root_key: u32,
+    layout: Value,
+    registry: PortableRegistry,
+}
+
+impl InkStorageLayout {
+    /// Load the layout from an ink! metadata file, i.e. `<contract>.json` or `<contract>.contract`.
+    pub fn from_metadata_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let metadata = contract_metadata::ContractMetadata::load(path)?;
+        Self::from_abi(&metadata.abi)
+    }
+
+    /// Load the layout from the ABI section of ink! metadata.
+    pub fn from_abi(abi: &Map<String, Value>) -> anyhow::Result<Self> {
+        let root = abi
+            .get("storage")
+            .and_then(|storage| storage.get("root"))
+            .ok_or_else(|| anyhow::anyhow!("No root storage layout in metadata"))?;
+        let root_key = root
+            .get("root_key")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("No root key in storage layout"))
+            .and_then(parse_key)?;
+        let layout = root
+            .get("layout")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No layout in root storage layout"))?;
+        let types = abi
+            .get("types")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No types in metadata"))?;
+        let registry = serde_json::from_value(serde_json::json!({ "types": types }))?;
+
+        Ok(Self {
+            root_key,
+            layout,
+            registry,
+        })
+    }
+
+    /// The key of the storage item the root struct is stored under, as passed to
+    /// `read_storage`.
+    pub fn root_key(&self) -> Vec<u8> {
+        self.root_key.encode()
+    }
+
+    /// Decode the fields of the root struct from the value stored under [`Self::root_key`].
+    pub fn decode_root(&self, mut value: &[u8]) -> anyhow::Result<Vec<(String, StorageField)>> {
+        match self.decode_layout(&self.layout, &mut value)? {
+            StorageField::Struct(fields) => Ok(fields),
+            _ => anyhow::bail!("Root storage layout is not a struct"),
+        }
+    }
+
+    fn decode_layout(&self, layout: &Value, value: &mut &[u8]) -> anyhow::Result<StorageField> {
+        if let Some(leaf) = layout.get("leaf") {
+            let ty = leaf
+                .get("ty")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow::anyhow!("No type for leaf layout"))?;
+            let decoded = scale_value::scale::decode_as_type(value, ty as u32, &self.registry)
+                .map_err(|err| anyhow::anyhow!("Error decoding type {ty}: {err:?}"))?;
+            return Ok(StorageField::Value(decoded));
+        }
+        if let Some(root) = layout.get("root") {
+            let root_key = root
+                .get("root_key")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("No root key in root layout"))
+                .and_then(parse_key)?;
+            return Ok(StorageField::Lazy { root_key });
+        }
+        if let Some(layout) = layout.get("struct") {
+            return Ok(StorageField::Struct(self.decode_fields(layout, value)?));
+        }
+        if let Some(layout) = layout.get("enum") {
+            let (&discriminant, rest) = value
+                .split_first()
+                .ok_or_else(|| anyhow::anyhow!("No discriminant for enum layout"))?;
+            *value = rest;
+            let layout = layout
+                .get("variants")
+                .and_then(|variants| variants.get(discriminant.to_string()))
+                .ok_or_else(|| anyhow::anyhow!("No variant {discriminant} in enum layout"))?;
+            let variant = layout
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let fields = self.decode_fields(layout, value)?;
+            return Ok(StorageField::Enum { variant, fields });
+        }
+        if let Some(layout) = layout.get("array") {
+            let len = layout
+                .get("len")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow::anyhow!("No length for array layout"))?;
+            let layout = layout
+                .get("layout")
+                .ok_or_else(|| anyhow::anyhow!("No element layout for array layout"))?;
+            let elements = (0..len)
+                .map(|_| self.decode_layout(layout, value))
+                .collect::<anyhow::Result<_>>()?;
+            return Ok(StorageField::Array(elements));
+        }
+        anyhow::bail!("Unsupported storage layout {layout}")
+    }
+
+    /// Decode the fields of a struct layout, or of an enum variant.
+    fn decode_fields(
+        &self,
+        layout: &Value,
+        value: &mut &[u8],
+    ) -> anyhow::Result<Vec<(String, StorageField)>> {
+        let fields = layout
+            .get("fields")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow::anyhow!("No fields in struct layout"))?;
+        fields
+            .iter()
+            .map(|field| {
+                let name = field
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                let layout = field
+                    .get("layout")
+                    .ok_or_else(|| anyhow::anyhow!("No layout for field {name}"))?;
+                Ok((name, self.decode_layout(layout, value)?))
+            })
+            .collect()
+    }
+}
+
+/// Parse a `0x` prefixed hex encoded little endian `u32` storage key.
+fn parse_key(key: &str) -> anyhow::Result<u32> {
+    let bytes = hex::decode(key.trim_start_matches("0x"))?;
+    let bytes: [u8; 4] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid storage key {key}"))?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_ink_root_struct() {
+        let abi = serde_json::json!({
+            "storage": {
+                "root": {
+                    "root_key": "0x00000000",
+                    "layout": {
+                        "struct": {
+                            "name": "Flipper",
+                            "fields": [
+                                { "name": "value", "layout": { "leaf": { "key": "0x00000000", "ty": 0 } } },
+                                { "name": "count", "layout": { "leaf": { "key": "0x00000000", "ty": 1 } } },
+                                { "name": "balances", "layout": { "root": { "root_key": "0x2a000000" } } }
+                            ]
+                        }
+                    }
+                }
+            },
+            "types": [
+                { "id": 0, "type": { "def": { "primitive": "bool" } } },
+                { "id": 1, "type": { "def": { "primitive": "u32" } } }
+            ]
+        });
+        let layout = InkStorageLayout::from_abi(abi.as_object().unwrap()).unwrap();
+        assert_eq!(layout.root_key(), vec![0, 0, 0, 0]);
+
+        let fields = layout.decode_root(&(true, 7u32).encode()).unwrap();
+        let names = fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["value", "count", "balances"]);
+        assert!(matches!(fields[0].1, StorageField::Value(ref v) if v.as_bool() == Some(true)));
+        assert!(matches!(fields[1].1, StorageField::Value(ref v) if v.as_u128() == Some(7)));
+        assert!(matches!(fields[2].1, StorageField::Lazy { root_key: 42 }));
+    }
+    #[test]
+    fn decodes_ink_enums_and_arrays() {
+        let leaf = serde_json::json!({ "leaf": { "key": "0x00000000", "ty": 0 } });
+        let abi = serde_json::json!({
+            "storage": {
+                "root": {
+                    "root_key": "0x00000000",
+                    "layout": {
+                        "struct": {
+                            "name": "Auction",
+                            "fields": [
+                                { "name": "status", "layout": { "enum": {
+                                    "dispatchKey": "0x00000000",
+                                    "name": "Status",
+                                    "variants": {
+                                        "0": { "name": "Pending", "fields": [] },
+                                        "1": { "name": "Ended", "fields": [{ "name": "0", "layout": leaf }] }
+                                    }
+                                } } },
+                                { "name": "bids", "layout": { "array": {
+                                    "offset": "0x00000000",
+                                    "len": 2,
+                                    "layout": leaf
+                                } } }
+                            ]
+                        }
+                    }
+                }
+            },
+            "types": [{ "id": 0, "type": { "def": { "primitive": "u32" } } }]
+        });
+        let layout = InkStorageLayout::from_abi(abi.as_object().unwrap()).unwrap();
+
+        let value = (1u8, 3u32, [5u32, 8u32]).encode();
+        let fields = layout.decode_root(&value).unwrap();
+        match &fields[0].1 {
+            StorageField::Enum { variant, fields } => {
+                assert_eq!(variant, "Ended");
+                assert!(
+                    matches!(fields[0].1, StorageField::Value(ref v) if v.as_u128() == Some(3))
+                );
+            }
+            field => panic!("Expected an enum, got {field:?}"),
+        }
+        match &fields[1].1 {
+            StorageField::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| match element {
+                        StorageField::Value(v) => v.as_u128(),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(elements, [Some(5), Some(8)]);
+            }
+            field => panic!("Expected an array, got {field:?}"),
+        }
+
+        let err = layout.decode_root(&(2u8,).encode()).unwrap_err();
+        assert!(err.to_string().contains("No variant 2"), "{err}");
+    }
+}
@@ -0,0 +1,273 @@
+//! Host function call tracing for `pallet-contracts`.
+//!
+//! When the `runtime::contracts::strace` log target is enabled at `TRACE` level, `pallet-contracts`
+//! appends a line to the contract debug buffer for every host function invoked by the contract,
+//! e.g. `seal0::hash_sha2_256(input_ptr: 65552, input_len: 4, output_ptr: 65536) = Ok(())`.
+//!
+//! [`with_strace`] enables that target for the duration of a closure, and [`HostFnTrace`]
+//! aggregates the resulting lines into a per host function breakdown of call counts, the
+//! benchmarked weights of the `Schedule` and wall clock time.
+//!
+//! The trace lines carry no timestamps. Instead `pallet-contracts` checks whether the target is
+//! enabled right before it executes each host function, and [`HostFnClock`] records the time of
+//! these checks. A host function is timed from being entered until the contract enters the next
+//! one, or the call returns, so its time includes the contract code executed in between.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tracing::{level_filters::LevelFilter, Metadata};
+use tracing_subscriber::layer::{Context, Filter};
+
+use crate::{drink::Weight, logging};
+
+/// The log target under which `pallet-contracts` traces host function calls.
+pub const STRACE_TARGET: &str = "runtime::contracts::strace";
+
+/// The benchmarked base weight of each host function, keyed by function name.
+pub type HostFnWeights = BTreeMap<String, Weight>;
+
+/// Execute `f` with host function tracing enabled.
+///
+/// Host function tracing is driven by the `log` crate, which is forwarded to a subscriber enabling
+/// [`STRACE_TARGET`] on the current thread. Use [`logging::scoped`] with a [`HostFnClock`] to
+/// capture logs at the same time.
+pub fn with_strace<T>(f: impl FnOnce() -> T) -> T {
+    logging::scoped(None, Some(HostFnClock::default()), f).0
+}
+
+/// A filter enabling [`STRACE_TARGET`], which records the time each host function is entered.
+#[derive(Debug, Default, Clone)]
+pub struct HostFnClock(Arc<Mutex<Vec<Instant>>>);
+
+impl HostFnClock {
+    /// The time from entering each host function until entering the next one, or until `end` for
+    /// the last one, in the order the host functions were entered.
+    pub fn durations(&self, end: Instant) -> Vec<Duration> {
+        let entered = self.0.lock().expect("poisoned host function clock");
+        entered
+            .iter()
+            .zip(entered.iter().skip(1).chain(std::iter::once(&end)))
+            .map(|(entered, next)| next.saturating_duration_since(*entered))
+            .collect()
+    }
+}
+
+impl<S> Filter<S> for HostFnClock {
+    fn enabled(&self, metadata: &Metadata<'_>, _: &Context<'_, S>) -> bool {
+        let enabled = metadata.target() == STRACE_TARGET;
+        if enabled {
+            self.0
+                .lock()
+                .expect("poisoned host function clock")
+                .push(Instant::now());
+        }
+        enabled
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::TRACE)
+    }
+}
+
+/// Extract the base weight of each host function from the given contracts `Schedule`.
+///
+/// Weights are keyed by the name `pallet-contracts` uses for the host function, without any
+/// `seal_` prefix.
+pub fn host_fn_weights<S: serde::Serialize>(schedule: &S) -> anyhow::Result<HostFnWeights> {
+    let schedule = serde_json::to_value(schedule)?;
+    let weights = schedule
+        .get("host_fn_weights")
+        .and_then(|weights| weights.as_object())
+        .ok_or(anyhow::anyhow!("Schedule does not contain host_fn_weights"))?;
+
+    Ok(weights
+        .iter()
+        .filter_map(|(name, weight)| {
+            let ref_time = weight.get("ref_time")?.as_u64()?;
+            let proof_size = weight.get("proof_size")?.as_u64()?;
+            Some((name.clone(), Weight::from_parts(ref_time, proof_size)))
+        })
+        .collect())
+}
+
+/// Aggregated host function invocations of a single contract call.
+#[derive(Debug, Default, Clone)]
+pub struct HostFnTrace {
+    /// Invocation statistics, keyed by `module::function`.
+    pub host_fns: BTreeMap<String, HostFnStats>,
+    /// The wall clock time of the whole call, including host function execution.
+    pub elapsed: Duration,
+}
+
+/// Invocation statistics for a single host function.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HostFnStats {
+    /// The number of times the host function was called.
+    pub count: u32,
+    /// The number of calls which returned an error.
+    pub errors: u32,
+    /// The benchmarked base weight of all calls, as charged by the `Schedule`.
+    ///
+    /// This excludes any per byte component, and is zero if the host function has no
+    /// corresponding entry in the `Schedule`.
+    pub weight: Weight,
+    /// The wall clock time of all calls, each timed until the next host function is entered.
+    ///
+    /// `None` if the calls were not timed, e.g. because the contract called another contract.
+    pub elapsed: Option<Duration>,
+}
+
+impl HostFnTrace {
+    /// Parse the host function trace lines from a contract debug buffer, timing them with the
+    /// `durations` of a [`HostFnClock`].
+    ///
+    /// Returns the remaining debug output which was not produced by the tracer, e.g. output of
+    /// `debug_println!` in the contract.
+    pub fn parse(
+        &mut self,
+        debug_message: &str,
+        weights: &HostFnWeights,
+        durations: &[Duration],
+    ) -> String {
+        let mut remaining = String::new();
+        let mut calls = Vec::new();
+        for line in debug_message.lines() {
+            match parse_strace_line(line) {
+                Some(call) => calls.push(call),
+                None => {
+                    remaining.push_str(line);
+                    remaining.push('\n');
+                }
+            }
+        }
+
+        // a line is written when the host function returns, so the lines of a called contract
+        // precede the line of the host function calling it, unlike the order they are entered in
+        let timed = calls.len() == durations.len()
+            && !calls.iter().any(|(_, name, _)| calls_contract(name));
+        for (i, (module, name, is_err)) in calls.into_iter().enumerate() {
+            let weight = weights
+                .get(name.trim_start_matches("seal_"))
+                .copied()
+                .unwrap_or_default();
+            let stats = self
+                .host_fns
+                .entry(format!("{module}::{name}"))
+                .or_default();
+            stats.count += 1;
+            stats.weight = stats.weight.saturating_add(weight);
+            if is_err {
+                stats.errors += 1;
+            }
+            if timed {
+                *stats.elapsed.get_or_insert(Duration::ZERO) += durations[i];
+            }
+        }
+        remaining
+    }
+
+    /// The total number of host function calls.
+    pub fn total_calls(&self) -> u32 {
+        self.host_fns.values().map(|stats| stats.count).sum()
+    }
+
+    /// The total benchmarked base weight of all host function calls.
+    pub fn total_weight(&self) -> Weight {
+        self.host_fns.values().fold(Weight::zero(), |acc, stats| {
+            acc.saturating_add(stats.weight)
+        })
+    }
+}
+
+/// Whether the host function `name` executes another contract.
+fn calls_contract(name: &str) -> bool {
+    matches!(
+        name.trim_start_matches("seal_"),
+        "call" | "delegate_call" | "instantiate"
+    )
+}
+
+/// Parse a line of the form `module::name(args..) = result`.
+///
+/// Returns the module, function name and whether the call returned an error.
+fn parse_strace_line(line: &str) -> Option<(&str, &str, bool)> {
+    let (module, rest) = line.split_once("::")?;
+    if !(module.starts_with("seal") || module == "__unstable__") {
+        return None;
+    }
+    let (name, rest) = rest.split_once('(')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let (_, result) = rest.rsplit_once(") = ")?;
+    Some((module, name, result.starts_with("Err")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_host_fn_calls() {
+        let debug_message = "\
+seal0::input(out_ptr: 65536, out_len_ptr: 65532) = Ok(())
+iterations: 2
+seal0::hash_sha2_256(input_ptr: 65552, input_len: 4, output_ptr: 65536) = Ok(())
+seal0::hash_sha2_256(input_ptr: 65552, input_len: 4, output_ptr: 65536) = Ok(())
+seal1::get_storage(key_ptr: 1, key_len: 4, out_ptr: 2, out_len_ptr: 3) = Ok(KeyNotFound)
+seal0::seal_return(flags: 0, data_ptr: 65536, data_len: 5) = Err(TrapReason::Return(..))
+";
+        let weights = HostFnWeights::from([
+            ("hash_sha2_256".to_owned(), Weight::from_parts(10, 1)),
+            ("return".to_owned(), Weight::from_parts(5, 0)),
+        ]);
+
+        let durations = [1, 2, 3, 4, 5].map(Duration::from_millis);
+
+        let mut trace = HostFnTrace::default();
+        let remaining = trace.parse(debug_message, &weights, &durations);
+
+        assert_eq!(remaining, "iterations: 2\n");
+        assert_eq!(trace.total_calls(), 5);
+        assert_eq!(
+            trace.host_fns["seal0::hash_sha2_256"],
+            HostFnStats {
+                count: 2,
+                errors: 0,
+                weight: Weight::from_parts(20, 2),
+                elapsed: Some(Duration::from_millis(5)),
+            }
+        );
+        assert_eq!(trace.host_fns["seal0::seal_return"].errors, 1);
+        assert_eq!(trace.host_fns["seal1::get_storage"].weight, Weight::zero());
+        // `seal_return` is looked up as `return`
+        assert_eq!(
+            trace.host_fns["seal0::seal_return"].weight,
+            Weight::from_parts(5, 0)
+        );
+        assert_eq!(trace.total_weight(), Weight::from_parts(25, 2));
+
+        // not timed if the number of host functions entered differs
+        let mut trace = HostFnTrace::default();
+        trace.parse(debug_message, &weights, &durations[1..]);
+        assert_eq!(trace.host_fns["seal0::input"].elapsed, None);
+    }
+
+    #[test]
+    fn does_not_time_calls_into_other_contracts() {
+        let debug_message = "\
+seal0::input(out_ptr: 65536, out_len_ptr: 65532) = Ok(())
+seal2::call(flags: 0, callee_ptr: 1, ref_time_limit: 0, proof_size_limit: 0, deposit_ptr: 2, value_ptr: 3, input_data_ptr: 4, input_data_len: 5, output_ptr: 6, output_len_ptr: 7) = Ok(Success)
+";
+        let durations = [1, 2].map(Duration::from_millis);
+
+        let mut trace = HostFnTrace::default();
+        trace.parse(debug_message, &HostFnWeights::new(), &durations);
+        assert_eq!(trace.total_calls(), 2);
+        assert_eq!(trace.host_fns["seal2::call"].elapsed, None);
+    }
+}
@@ -0,0 +1,390 @@
+//! Discovery, version checking and provisioning of the Solidity compilers.
+//!
+//! A compiler is resolved from the first of the following which is set or exists:
+//! 1. the `SCHLAU_SOLC` / `SCHLAU_SOLANG` environment variable,
+//! 2. the `solc` / `solang` key of the `schlau.toml` config file in the crate root,
+//! 3. the `PATH`,
+//! 4. the `bin/` directory in the crate root,
+//! 5. the cache of compilers installed with [`install_from_archive`].
+//!
+//! An explicitly configured compiler must satisfy the required version, other candidates are
+//! skipped if they don't. If no candidate is found but an archive is configured with
+//! `SCHLAU_SOLC_ARCHIVE` / `SCHLAU_SOLANG_ARCHIVE` (or `solc_archive` / `solang_archive`), the
+//! pinned version is installed from it into the cache.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
+
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+
+/// The prefix of the environment variables overriding the config file.
+pub const ENV_PREFIX: &str = "SCHLAU";
+
+static RESOLVED: Mutex<BTreeMap<Tool, PathBuf>> = Mutex::new(BTreeMap::new());
+
+/// A compiler binary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tool {
+    Solc,
+    Solang,
+}
+
+impl Tool {
+    /// The name of the binary.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Solc => "solc",
+            Tool::Solang => "solang",
+        }
+    }
+
+    /// The range of versions the benchmarks are known to work with.
+    pub fn required_version(&self) -> VersionReq {
+        let req = match self {
+            Tool::Solc => ">=0.8.20, <0.9",
+            Tool::Solang => ">=0.3.3, <0.4",
+        };
+        VersionReq::parse(req).expect("valid version requirement")
+    }
+
+    /// The version installed by [`install_from_archive`].
+    pub fn pinned_version(&self) -> Version {
+        match self {
+            Tool::Solc => Version::new(0, 8, 23),
+            Tool::Solang => Version::new(0, 3, 3),
+        }
+    }
+
+    fn env_var(&self) -> String {
+        format!("{ENV_PREFIX}_{}", self.name().to_uppercase())
+    }
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The toolchain configuration, loaded from `schlau.toml` and `SCHLAU_*` environment variables.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ToolchainConfig {
+    pub solc: Option<PathBuf>,
+    pub solang: Option<PathBuf>,
+    pub solc_archive: Option<PathBuf>,
+    pub solang_archive: Option<PathBuf>,
+    /// The directory compilers are installed to, defaults to `target/toolchain` in the crate root.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl ToolchainConfig {
+    /// Load the config file `schlau.toml` in the crate root if it exists, overridden by the
+    /// environment variables.
+    pub fn load() -> anyhow::Result<Self> {
+        let config = config::Config::builder()
+            .add_source(config::File::from(crate_dir().join("schlau")).required(false))
+            .add_source(config::Environment::with_prefix(ENV_PREFIX))
+            .build()?;
+        Ok(config.try_deserialize()?)
+    }
+
+    fn path(&self, tool: Tool) -> Option<&PathBuf> {
+        match tool {
+            Tool::Solc => self.solc.as_ref(),
+            Tool::Solang => self.solang.as_ref(),
+        }
+    }
+
+    fn archive(&self, tool: Tool) -> Option<&PathBuf> {
+        match tool {
+            Tool::Solc => self.solc_archive.as_ref(),
+            Tool::Solang => self.solang_archive.as_ref(),
+        }
+    }
+
+    pub fn cache_dir(&self) -> PathBuf {
+        self.cache_dir
+            .clone()
+            .unwrap_or_else(|| crate_dir().join("target").join("toolchain"))
+    }
+}
+
+/// The path to `tool`, resolved once per process.
+pub fn resolve(tool: Tool) -> anyhow::Result<PathBuf> {
+    let mut resolved = RESOLVED.lock().expect("poisoned toolchain cache");
+    if let Some(path) = resolved.get(&tool) {
+        return Ok(path.clone());
+    }
+    let path = resolve_with(tool, &ToolchainConfig::load()?)?;
+    resolved.insert(tool, path.clone());
+    Ok(path)
+}
+
+/// Resolve `tool` with the given `config`, see the module docs for the order of precedence.
+pub fn resolve_with(tool: Tool, config: &ToolchainConfig) -> anyhow::Result<PathBuf> {
+    let required = tool.required_version();
+
+    if let Some(path) = config.path(tool) {
+        let version = version(path).map_err(|err| {
+            anyhow::anyhow!(
+                "{tool} configured via {} or schlau.toml: {err}",
+                tool.env_var()
+            )
+        })?;
+        anyhow::ensure!(
+            required.matches(&version),
+            "{tool} {version} at {} does not satisfy the required version {required}",
+            path.display()
+        );
+        return Ok(path.clone());
+    }
+
+    let mut rejected = Vec::new();
+    for candidate in candidates(tool, config) {
+        match version(&candidate) {
+            Ok(version) if required.matches(&version) => return Ok(candidate),
+            Ok(version) => rejected.push(format!("{}: version {version}", candidate.display())),
+            Err(err) => rejected.push(format!("{}: {err}", candidate.display())),
+        }
+    }
+
+    if let Some(archive) = config.archive(tool) {
+        return install_from_archive(tool, archive, &config.cache_dir());
+    }
+
+    let mut message = format!(
+        "No {tool} satisfying {required} found. Set {} or `{tool}` in schlau.toml to its path, put \
+         it on the PATH or in {}, or set {}_ARCHIVE to install {} from a local archive.",
+        tool.env_var(),
+        crate_dir().join("bin").display(),
+        tool.env_var(),
+        tool.pinned_version(),
+    );
+    if !rejected.is_empty() {
+        message.push_str("\nRejected candidates:");
+        for candidate in rejected {
+            message.push_str(&format!("\n  {candidate}"));
+        }
+    }
+    Err(anyhow::anyhow!(message))
+}
+
+/// The existing binaries of `tool` on the `PATH`, in `bin/` and in the cache.
+fn candidates(tool: Tool, config: &ToolchainConfig) -> Vec<PathBuf> {
+    let mut dirs = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+    dirs.push(crate_dir().join("bin"));
+    dirs.push(installed_dir(
+        tool,
+        &tool.pinned_version(),
+        &config.cache_dir(),
+    ));
+
+    dirs.into_iter()
+        .map(|dir| dir.join(tool.name()))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// The version reported by the binary at `path`.
+pub fn version(path: &Path) -> anyhow::Result<Version> {
+    let output = Command::new(path)
+        .arg("--version")
+        .output()
+        .map_err(|err| anyhow::anyhow!("Failed to execute {}: {err}", path.display()))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "{} --version exited with {}",
+        path.display(),
+        output.status
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_version(&stdout)
+        .ok_or_else(|| anyhow::anyhow!("No version in output of {}: {stdout}", path.display()))
+}
+
+/// Parse the first version in the `--version` output, ignoring a `v` prefix, pre-release and
+/// build metadata, e.g. `Version: 0.8.23+commit.f704f362.Linux.g++` or `solang version v0.3.3`.
+fn parse_version(output: &str) -> Option<Version> {
+    output.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches('v');
+        let core = word.split(['+', '-']).next()?;
+        Version::parse(core).ok()
+    })
+}
+
+/// Install the pinned version of `tool` from the `.tar.gz` archive at `archive` into
+/// `cache_dir`, returning the path to the installed binary.
+///
+/// The archive must contain a binary named like the tool, at any depth.
+pub fn install_from_archive(
+    tool: Tool,
+    archive: &Path,
+    cache_dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let pinned = tool.pinned_version();
+    let install_dir = installed_dir(tool, &pinned, cache_dir);
+    let installed = install_dir.join(tool.name());
+    if installed.is_file() {
+        return Ok(installed);
+    }
+
+    let file = fs::File::open(archive).map_err(|err| {
+        anyhow::anyhow!("Failed to open {tool} archive {}: {err}", archive.display())
+    })?;
+    let unpack_dir = cache_dir.join(format!("{}-{pinned}.unpack", tool.name()));
+    if unpack_dir.exists() {
+        fs::remove_dir_all(&unpack_dir)?;
+    }
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(&unpack_dir)
+        .map_err(|err| anyhow::anyhow!("Failed to unpack {}: {err}", archive.display()))?;
+
+    let binary = find_file(&unpack_dir, tool.name())?
+        .ok_or_else(|| anyhow::anyhow!("No `{tool}` binary in archive {}", archive.display()))?;
+    make_executable(&binary)?;
+    let unpacked = version(&binary)?;
+    anyhow::ensure!(
+        unpacked == pinned,
+        "Archive {} contains {tool} {unpacked}, expected the pinned version {pinned}",
+        archive.display()
+    );
+
+    fs::create_dir_all(&install_dir)?;
+    fs::rename(&binary, &installed)?;
+    fs::remove_dir_all(&unpack_dir)?;
+    Ok(installed)
+}
+
+fn installed_dir(tool: Tool, version: &Version, cache_dir: &Path) -> PathBuf {
+    cache_dir.join(format!("{}-{version}", tool.name()))
+}
+
+fn find_file(dir: &Path, name: &str) -> anyhow::Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, name)? {
+                return Ok(Some(found));
+            }
+        } else if path.file_name().is_some_and(|file_name| file_name == name) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// The root of this crate, so compilers and other files of the crate are found regardless of the
+/// working directory.
+pub(crate) fn crate_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compiler_versions() {
+        let solc = "solc, the solidity compiler commandline interface\n\
+                    Version: 0.8.23+commit.f704f362.Linux.g++\n";
+        assert_eq!(parse_version(solc), Some(Version::new(0, 8, 23)));
+        assert_eq!(
+            parse_version("solang version v0.3.3-12-g1e7c3cb"),
+            Some(Version::new(0, 3, 3))
+        );
+        assert_eq!(parse_version("no version"), None);
+
+        assert!(Tool::Solc
+            .required_version()
+            .matches(&Tool::Solc.pinned_version()));
+        assert!(Tool::Solang
+            .required_version()
+            .matches(&Tool::Solang.pinned_version()));
+    }
+
+    #[test]
+    fn rejects_configured_compiler_that_does_not_exist() {
+        let config = ToolchainConfig {
+            solang: Some(PathBuf::from("/does/not/exist/solang")),
+            ..Default::default()
+        };
+        let err = resolve_with(Tool::Solang, &config).unwrap_err().to_string();
+        assert!(err.contains("SCHLAU_SOLANG"), "{err}");
+    }
+
+    /// Write a `.tar.gz` archive to `path`, containing a `tool` script that prints `version`.
+    #[cfg(unix)]
+    fn write_archive(path: &Path, tool: Tool, version: &str) {
+        let script = format!("#!/bin/sh\necho \"{tool} version v{version}\"\n");
+        let mut header = tar::Header::new_gnu();
+        header.set_size(script.len() as u64);
+        // not executable, the installer has to fix the permissions
+        header.set_mode(0o644);
+        let gz = flate2::write::GzEncoder::new(
+            fs::File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(gz);
+        builder
+            .append_data(
+                &mut header,
+                format!("dist/bin/{}", tool.name()),
+                script.as_bytes(),
+            )
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn installs_pinned_version_from_archive() {
+        let dir = std::env::temp_dir().join(format!("schlau-toolchain-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let cache_dir = dir.join("cache");
+        let pinned = Tool::Solang.pinned_version();
+        let install_dir = installed_dir(Tool::Solang, &pinned, &cache_dir);
+
+        let mismatching = dir.join("mismatching.tar.gz");
+        write_archive(&mismatching, Tool::Solang, "0.3.2");
+        let err = install_from_archive(Tool::Solang, &mismatching, &cache_dir)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(&format!(
+                "contains solang 0.3.2, expected the pinned version {pinned}"
+            )),
+            "{err}"
+        );
+        assert!(!install_dir.exists());
+
+        let matching = dir.join("matching.tar.gz");
+        write_archive(&matching, Tool::Solang, &pinned.to_string());
+        let installed = install_from_archive(Tool::Solang, &matching, &cache_dir).unwrap();
+        assert_eq!(installed, install_dir.join("solang"));
+        assert_eq!(version(&installed).unwrap(), pinned);
+        assert!(!cache_dir.join(format!("solang-{pinned}.unpack")).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -15,6 +15,203 @@ mod tests {
         assert_eq!(returndata, contract.sandbox.call(args).unwrap());
     }
 
+    #[cfg(feature = "evm")]
+    #[test]
+    fn evm_top_up_and_nonce_tracking() {
+        use schlau::evm::{EvmContract, FeeMode, ACCOUNTS};
+
+        let mut contract = EvmContract::init("Computation").with_fee_mode(FeeMode::TopUp);
+        let param = alloy_primitives::I256::try_from(100i64).unwrap();
+        let args = contract.call_args("triangle_number", &[DynSolValue::Int(param, 64)]);
+
+        let balance = contract.sandbox.free_balance(ACCOUNTS[0]);
+        let nonce = contract.sandbox.nonce(ACCOUNTS[0]);
+        for _ in 0..3 {
+            contract.sandbox.call(args.clone()).unwrap();
+        }
+        assert_eq!(balance, contract.sandbox.free_balance(ACCOUNTS[0]));
+        assert_eq!(nonce + 3, contract.sandbox.nonce(ACCOUNTS[0]));
+    }
+
+    #[cfg(feature = "evm")]
+    #[test]
+    fn evm_nonce_tracking_after_revert() {
+        use schlau::evm::{CreateArgs, EvmContract, ACCOUNTS};
+
+        let mut contract = EvmContract::init("Counter");
+        let nonce = contract.sandbox.nonce(ACCOUNTS[0]);
+
+        // PUSH1 0 PUSH1 0 REVERT
+        let create_args = CreateArgs {
+            source: ACCOUNTS[0],
+            init: vec![0x60, 0x00, 0x60, 0x00, 0xfd],
+            gas_limit: 1_000_000,
+            max_fee_per_gas: contract.call_args("count", &[]).max_fee_per_gas,
+            ..Default::default()
+        };
+        assert!(contract.sandbox.create(create_args).is_err());
+        assert_eq!(nonce + 1, contract.sandbox.nonce(ACCOUNTS[0]));
+
+        let args = contract.call_args("increment", &[DynSolValue::Uint(1u8.into(), 32)]);
+        contract.sandbox.call(args).unwrap();
+        assert_eq!(nonce + 2, contract.sandbox.nonce(ACCOUNTS[0]));
+    }
+
+    #[cfg(feature = "evm")]
+    #[test]
+    fn evm_call_traced() {
+        use schlau::evm::{tracer::TracerConfig, EvmContract};
+
+        let mut contract = EvmContract::init("Computation");
+        let param = alloy_primitives::I256::try_from(100i64).unwrap();
+        let args = contract.call_args("triangle_number", &[DynSolValue::Int(param, 64)]);
+
+        let returndata = contract.sandbox.call(args.clone()).unwrap();
+        let trace = contract
+            .sandbox
+            .call_traced(args, TracerConfig::default())
+            .unwrap();
+        assert!(!trace.failed);
+        assert_eq!(returndata, trace.return_value);
+        assert!(!trace.struct_logs.is_empty());
+        assert!(trace.opcodes["ADD"].count >= 100);
+    }
+
+    #[cfg(feature = "evm")]
+    #[test]
+    fn evm_precompiles() {
+        use fp_evm::{ExitSucceed, PrecompileOutput};
+        use schlau::{
+            accounts::Accounts,
+            evm::{
+                precompiles::{register_precompile, unregister_precompile},
+                CallArgs, EvmContract, ACCOUNTS,
+            },
+        };
+        use sp_core::{keccak_256, H160};
+
+        let mut contract = EvmContract::init("Counter");
+        let count_args = contract.call_args("count", &[]);
+        let call_args = |target: u64, input: Vec<u8>| CallArgs {
+            source: ACCOUNTS[0],
+            target: H160::from_low_u64_be(target),
+            input,
+            gas_limit: 1_000_000,
+            ..count_args.clone()
+        };
+
+        // ecrecover returns the signer's address, left padded to 32 bytes
+        let accounts = Accounts::new("schlau", 1).unwrap();
+        let signer = accounts.evm_account(0);
+        let hash = keccak_256(b"schlau");
+        let signature = signer.pair.sign_prehashed(&hash).0;
+        let mut input = hash.to_vec();
+        input.extend([0; 31]);
+        input.push(27 + signature[64]);
+        input.extend(&signature[..64]);
+        let output = contract.sandbox.call(call_args(1, input)).unwrap();
+        assert_eq!(&output[12..], signer.address.as_bytes());
+
+        // modexp of 3^5 mod 7, with 1 byte base, exponent and modulus
+        let mut input = Vec::new();
+        for len in [1u8; 3] {
+            input.extend([0; 31]);
+            input.push(len);
+        }
+        input.extend([3, 5, 7]);
+        let output = contract.sandbox.call(call_args(5, input)).unwrap();
+        assert_eq!(output, [5]);
+
+        register_precompile(H160::from_low_u64_be(0x400), |handle| {
+            Ok(PrecompileOutput {
+                exit_status: ExitSucceed::Returned,
+                output: handle.input().iter().rev().copied().collect(),
+            })
+        });
+        let output = contract
+            .sandbox
+            .call(call_args(0x400, vec![1, 2, 3]))
+            .unwrap();
+        assert_eq!(output, [3, 2, 1]);
+
+        // without the precompile the address is an account without code
+        unregister_precompile(H160::from_low_u64_be(0x400));
+        let output = contract
+            .sandbox
+            .call(call_args(0x400, vec![1, 2, 3]))
+            .unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn counter_storage() {
+        #[cfg(feature = "evm")]
+        {
+            let mut contract = schlau::evm::EvmContract::init("Counter");
+            let args = contract.call_args("increment", &[DynSolValue::Uint(5u8.into(), 32)]);
+            contract.sandbox.call(args).unwrap();
+
+            let slot = contract
+                .sandbox
+                .read_storage(contract.address(), sp_core::H256::zero());
+            assert_eq!(sp_core::H256::from_low_u64_be(5), slot);
+            assert_eq!(1, contract.sandbox.dump_storage(contract.address()).items());
+
+            // the EVM has no storage deposit, but overwriting slot 0 still costs fees
+            let args = contract.call_args("increment", &[DynSolValue::Uint(1u8.into(), 32)]);
+            let cost = contract.sandbox.call_with_outcome(args).unwrap().cost;
+            assert_eq!(0, cost.storage_deposit);
+            assert!(cost.caller_balance < 0);
+        }
+
+        #[cfg(any(feature = "wasm", feature = "riscv"))]
+        {
+            let mut contract = schlau::solang::SolangContract::init("Counter");
+            let account = contract.contract_account();
+            let before = contract.drink_api.dump_storage(&account).unwrap();
+
+            let args = contract.call_args("increment", 5u32);
+            let cost = contract.drink_api.call_with_outcome(args).unwrap().cost;
+            assert!(cost.storage_deposit > 0);
+            assert_eq!(-cost.storage_deposit, cost.caller_balance);
+
+            let after = contract.drink_api.dump_storage(&account).unwrap();
+            assert_ne!(before, after);
+            assert!(after.footprint() > 0);
+        }
+    }
+
+    #[test]
+    fn estimate() {
+        #[cfg(feature = "evm")]
+        {
+            let mut contract = schlau::evm::EvmContract::init("Computation");
+            let param = alloy_primitives::I256::try_from(1_000i64).unwrap();
+            let args = contract.call_args("triangle_number", &[DynSolValue::Int(param, 64)]);
+
+            let gas = contract
+                .sandbox
+                .estimate_gas_validated(args.clone())
+                .unwrap();
+            assert!(gas < args.gas_limit);
+            let args = schlau::evm::CallArgs {
+                gas_limit: gas,
+                ..args
+            };
+            contract.sandbox.call(args).unwrap();
+        }
+
+        #[cfg(any(feature = "wasm", feature = "riscv"))]
+        {
+            let mut contract = schlau::solang::SolangContract::init("Computation");
+            let args = contract.call_args("triangle_number", 1_000i64);
+
+            let estimate = contract.drink_api.estimate_validated(args.clone()).unwrap();
+            let args = args.with_estimate(&estimate);
+            contract.drink_api.call(args).unwrap();
+        }
+    }
+
     #[cfg(any(feature = "wasm", feature = "riscv"))]
     fn test_solang<Args: Encode>(contract: &str, message: &str, args: &Args, returns: Vec<u8>) {
         let mut contract = schlau::solang::SolangContract::init(contract);
@@ -22,6 +219,332 @@ mod tests {
         assert_eq!(returns, contract.drink_api.call(args).unwrap());
     }
 
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn solang_on_contracts_runtime() {
+        use schlau::{
+            contracts::{ContractsConfig, ContractsRuntime},
+            drink_api::DrinkApi,
+            solang::SolangContract,
+        };
+
+        let config = ContractsConfig::default()
+            .with_deposit_per_byte(100)
+            .with_call_stack_depth(1);
+        let mut drink_api = DrinkApi::<ContractsRuntime>::new().with_config(config);
+        assert_eq!(drink_api.config().deposit_per_byte, 100);
+        assert_eq!(drink_api.config().call_stack_depth, 1);
+
+        let mut contract = SolangContract::init_with_api("Computation", drink_api);
+        let args = contract.call_args("triangle_number", 100i64);
+        assert_eq!(5050i64.encode(), contract.drink_api.call(args).unwrap());
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn wasm_execution_profile() {
+        use contract_build::Target;
+        use schlau::{
+            contracts::ContractsRuntime,
+            drink_api::{CallArgs, CreateArgs, DrinkApi},
+            profile::{CodeProfile, InstrumentedCode},
+            solang::build_and_load_contract,
+        };
+        use subxt_signer::sr25519::dev;
+
+        let build_result = build_and_load_contract("contracts/solidity/Computation.sol").unwrap();
+        let code_profile = CodeProfile::from_code(&build_result.code, Target::Wasm).unwrap();
+        assert!(code_profile.total_instructions() > 0);
+
+        let instrumented = InstrumentedCode::instrument(&build_result.code).unwrap();
+        let mut drink_api = DrinkApi::<ContractsRuntime>::new();
+        let create_args =
+            CreateArgs::<ContractsRuntime>::new(instrumented.code.clone(), dev::alice())
+                .with_data(build_result.constructor_selector("new").unwrap());
+        let (account, _) = instrumented.record(|| drink_api.instantiate_with_code(create_args));
+        let account = account.unwrap();
+
+        let mut triangle_number = |n: i64| {
+            let mut call_data = build_result.message_selector("triangle_number").unwrap();
+            call_data.extend(n.encode());
+            let args = CallArgs::<ContractsRuntime>::new(account.clone(), dev::alice(), call_data)
+                .with_max_gas_limit();
+            let (returns, profile) = instrumented.record(|| drink_api.call(args));
+            assert_eq!((n * (n + 1) / 2).encode(), returns.unwrap());
+            profile
+        };
+        let small = triangle_number(10);
+        let large = triangle_number(1_000);
+        assert!(small.total_instructions() > 0);
+        // every iteration of the loop executes at least a few instructions
+        assert!(large.total_instructions() > small.total_instructions() + 3 * 990);
+        assert_eq!(
+            small.functions.keys().collect::<Vec<_>>(),
+            large.functions.keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn preload_ink_constructor_args() {
+        use ink::env::DefaultEnvironment;
+        use preload::preload::{Preload, PreloadRef};
+        use schlau::{
+            drink::runtime::MinimalRuntime,
+            drink_api::CallArgs,
+            ink::{InkDrink, InstantiateOptions},
+        };
+        use subxt_signer::sr25519::dev;
+
+        let mut ink_drink = InkDrink::<DefaultEnvironment, MinimalRuntime>::new();
+        let instantiated = ink_drink.build_and_instantiate_with::<_, Preload, _, _>(
+            "contracts/ink/preload/Cargo.toml",
+            &mut PreloadRef::with_entries(10),
+            InstantiateOptions::new()
+                .with_value(1_000)
+                .with_salt(vec![1]),
+        );
+        assert!(ink_drink.drink.free_balance(&instantiated.account_id) >= 1_000);
+
+        let call_args =
+            CallArgs::from_call_builder(dev::alice(), &instantiated.call_builder.sum(10))
+                .with_max_gas_limit();
+        let expected = Result::<u32, ()>::encode(&Ok((0..10).map(|i| i * 2).sum()));
+        assert_eq!(expected, ink_drink.drink.call(call_args).unwrap());
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn preload_ink_from_metadata() {
+        use ink::env::DefaultEnvironment;
+        use schlau::{
+            drink::runtime::MinimalRuntime,
+            ink::{InkDrink, InstantiateOptions},
+        };
+        use serde_json::json;
+        use subxt_signer::sr25519::dev;
+
+        let mut ink_drink = InkDrink::<DefaultEnvironment, MinimalRuntime>::new();
+        let contract = ink_drink
+            .build_and_instantiate_from_metadata(
+                "contracts/ink/preload/Cargo.toml",
+                "with_entries",
+                &[json!(10)],
+                InstantiateOptions::new(),
+            )
+            .unwrap();
+
+        let entries = ink_drink
+            .call_from_metadata(&contract, dev::alice(), "entries", &[])
+            .unwrap();
+        assert_eq!(entries.to_string(), "Ok(10)");
+
+        let sum = ink_drink
+            .call_from_metadata(&contract, dev::alice(), "sum", &[json!(10)])
+            .unwrap();
+        assert_eq!(sum.to_string(), "Ok(90)");
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn preload_ink_storage() {
+        use ink::env::DefaultEnvironment;
+        use preload::preload::{Preload, PreloadRef};
+        use schlau::{
+            drink::runtime::MinimalRuntime,
+            ink::{build_contract_with_metadata, InkDrink, InstantiateOptions},
+            storage::{InkStorageLayout, StorageField},
+        };
+
+        let mut ink_drink = InkDrink::<DefaultEnvironment, MinimalRuntime>::new();
+        let instantiated = ink_drink.build_and_instantiate_with::<_, Preload, _, _>(
+            "contracts/ink/preload/Cargo.toml",
+            &mut PreloadRef::with_entries(10),
+            InstantiateOptions::new(),
+        );
+        let artifacts =
+            build_contract_with_metadata("contracts/ink/preload/Cargo.toml", schlau::target())
+                .unwrap();
+        let layout = InkStorageLayout::from_metadata_file(artifacts.bundle.unwrap()).unwrap();
+
+        let fields = ink_drink
+            .drink
+            .read_ink_root(instantiated.account_id.clone(), &layout)
+            .unwrap();
+        let names = fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["values", "entries"]);
+        assert!(matches!(fields[0].1, StorageField::Lazy { .. }));
+        assert!(matches!(fields[1].1, StorageField::Value(ref v) if v.as_u128() == Some(10)));
+
+        // the root storage item and one item per mapping entry
+        let dump = ink_drink
+            .drink
+            .dump_storage(&instantiated.account_id)
+            .unwrap();
+        assert_eq!(11, dump.items());
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn preload_ink_host_fn_trace() {
+        use ink::env::DefaultEnvironment;
+        use preload::preload::{Preload, PreloadRef};
+        use schlau::{drink::runtime::MinimalRuntime, drink_api::CallArgs, ink::InkDrink};
+        use std::time::Duration;
+        use subxt_signer::sr25519::dev;
+
+        let mut ink_drink = InkDrink::<DefaultEnvironment, MinimalRuntime>::new();
+        let contract = ink_drink.build_and_instantiate::<_, Preload, _, _>(
+            "contracts/ink/preload/Cargo.toml",
+            &mut PreloadRef::with_entries(10),
+        );
+        ink_drink.drink.set_host_fn_tracing(true).unwrap();
+        let call_args =
+            CallArgs::from_call_builder(dev::alice(), &contract.sum(10)).with_max_gas_limit();
+        let outcome = ink_drink.drink.call_with_outcome(call_args).unwrap();
+        let trace = outcome.host_fns.unwrap();
+
+        // the number of calls to `name`, with or without the `seal_` prefix
+        let count = |name: &str| -> u32 {
+            trace
+                .host_fns
+                .iter()
+                .filter(|(host_fn, _)| {
+                    host_fn
+                        .rsplit("::")
+                        .next()
+                        .map(|f| f.trim_start_matches("seal_"))
+                        == Some(name)
+                })
+                .map(|(_, stats)| stats.count)
+                .sum()
+        };
+        assert_eq!(count("input"), 1);
+        // the root storage and 10 values of the mapping
+        assert_eq!(count("get_storage"), 11);
+        assert_eq!(count("return"), 1);
+        // the trace lines are not part of the debug message
+        assert!(outcome.debug_message.is_empty());
+
+        let timed = trace
+            .host_fns
+            .values()
+            .map(|stats| stats.elapsed.expect("host functions are timed"))
+            .sum::<Duration>();
+        assert!(timed > Duration::ZERO);
+        assert!(timed <= trace.elapsed);
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn crypto_ink_debug_messages() {
+        use contract_build::BuildMode;
+        use crypto::crypto::{Crypto, CryptoRef};
+        use ink::env::DefaultEnvironment;
+        use schlau::{
+            drink::runtime::MinimalRuntime,
+            drink_api::CallArgs,
+            ink::{InkBuildOptions, InkDrink},
+        };
+        use subxt_signer::sr25519::dev;
+
+        // debug messages are only compiled into debug builds
+        let options = InkBuildOptions::default().with_build_mode(BuildMode::Debug);
+        let mut ink_drink =
+            InkDrink::<DefaultEnvironment, MinimalRuntime>::new().with_build_options(options);
+        ink_drink.drink.set_debug_log(true);
+        let contract = ink_drink.build_and_instantiate::<_, Crypto, _, _>(
+            "contracts/ink/crypto/Cargo.toml",
+            &mut CryptoRef::new(),
+        );
+        let call_args =
+            CallArgs::from_call_builder(dev::alice(), &contract.sha3(3)).with_max_gas_limit();
+
+        let outcome = ink_drink
+            .drink
+            .call_with_outcome(call_args.clone())
+            .unwrap();
+        outcome.assert_printed("iterations: 3");
+        ink_drink.drink.assert_printed("check debug");
+        ink_drink.drink.assert_printed("iterations: 3");
+
+        ink_drink.drink.set_debug_messages(false);
+        let outcome = ink_drink.drink.call_with_outcome(call_args).unwrap();
+        assert!(outcome.debug_message.is_empty());
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn instantiate_by_code_hash() {
+        use schlau::{
+            drink::runtime::{AccountIdFor, MinimalRuntime},
+            drink_api::{DrinkApi, InstantiateArgs},
+            solang::build_and_load_contract,
+        };
+        use subxt_signer::sr25519::dev;
+
+        let build_result = build_and_load_contract("contracts/solidity/Computation.sol").unwrap();
+        let data = build_result.constructor_selector("new").unwrap();
+
+        let mut drink_api = DrinkApi::<MinimalRuntime>::new();
+        let code_hash = drink_api
+            .upload_code(build_result.code.clone(), dev::alice())
+            .unwrap();
+
+        let deployer = AccountIdFor::<MinimalRuntime>::from(dev::alice().public_key().0);
+        let mut addresses = Vec::new();
+        for salt in [vec![0], vec![1]] {
+            let instantiate_args = InstantiateArgs::<MinimalRuntime>::new(code_hash, dev::alice())
+                .with_data(data.clone())
+                .with_salt(salt.clone());
+            let outcome = drink_api
+                .instantiate_with_outcome(instantiate_args)
+                .unwrap();
+            assert_eq!(
+                drink_api.contract_address(&deployer, &code_hash, &data, &salt),
+                outcome.account_id
+            );
+            // the caller pays the storage deposit of the new contract
+            assert!(outcome.cost.storage_deposit > 0);
+            assert!(outcome.cost.caller_balance <= -outcome.cost.storage_deposit);
+            addresses.push(outcome.account_id);
+        }
+        assert_ne!(addresses[0], addresses[1]);
+    }
+
+    #[cfg(any(feature = "wasm", feature = "riscv"))]
+    #[test]
+    fn chain_extension_echo() {
+        use chain_ext::{
+            chain_ext::{ChainExt, ChainExtRef},
+            EchoEnvironment, ECHO_EXTENSION_ID, ECHO_FUNCTION_ID,
+        };
+        use schlau::{
+            contracts::{chain_extension::register_chain_extension, ContractsRuntime},
+            drink_api::CallArgs,
+            ink::InkDrink,
+        };
+        use subxt_signer::sr25519::dev;
+
+        register_chain_extension(ECHO_EXTENSION_ID, ECHO_FUNCTION_ID, |input| {
+            Ok(input.to_vec())
+        });
+
+        let mut ink_drink = InkDrink::<EchoEnvironment, ContractsRuntime>::new();
+        let contract = ink_drink.build_and_instantiate::<_, ChainExt, _, _>(
+            "contracts/ink/chain_ext/Cargo.toml",
+            &mut ChainExtRef::new(),
+        );
+        let call_args =
+            CallArgs::from_call_builder(dev::alice(), &contract.echo(3)).with_max_gas_limit();
+
+        let expected = Result::<u32, ()>::encode(&Ok(3 * 32));
+        assert_eq!(expected, ink_drink.drink.call(call_args).unwrap());
+    }
+
     #[cfg(any(feature = "wasm", feature = "riscv"))]
     macro_rules! test_ink {
         ( $name:ident, $contract:ident, $contract_ref:ident, $message:ident, $args:ident, $returns:ident) => {
@@ -160,6 +683,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn square_root_with_remapped_import() {
+        let (contract, message) = ("SquareRoot", "sqrt_sum");
+        let param = 10u32;
+        let expected = 16u64;
+
+        #[cfg(feature = "evm")]
+        {
+            let args = [DynSolValue::Uint(alloy_primitives::U256::from(param), 32)];
+            let returndata = alloy_primitives::U256::from(expected)
+                .to_be_bytes::<32>()
+                .to_vec();
+
+            test_evm(contract, message, &args, returndata);
+        }
+
+        #[cfg(any(feature = "wasm", feature = "riscv"))]
+        {
+            test_solang(contract, message, &param, expected.encode());
+        }
+    }
+
     #[test]
     fn fibonacci() {
         let param = 320u32;
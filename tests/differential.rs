@@ -0,0 +1,104 @@
+//! Assert that all implementations of a workload agree with each other on all backends, and with
+//! a reference implementation where there is one.
+
+use alloy_dyn_abi::DynSolValue;
+use alloy_primitives::{I256, U256};
+use schlau::differential::{Differential, Outcome, Workload};
+
+fn int(n: i64, bits: usize) -> DynSolValue {
+    DynSolValue::Int(I256::try_from(n).unwrap(), bits)
+}
+
+fn uint(n: u64, bits: usize) -> DynSolValue {
+    DynSolValue::Uint(U256::from(n), bits)
+}
+
+#[test]
+fn odd_product() {
+    let workload = Workload::new("odd_product", "(int32)", "(int64)")
+        .unwrap()
+        .solidity("Computation")
+        .ink("computation");
+    let mut differential = Differential::new(workload).unwrap();
+
+    for n in [0, 1, 7, 1_000, 2_000_000] {
+        let expected = (1..=n as i64).fold(1i64, |prod, x| prod.wrapping_mul(2 * x - 1));
+        differential
+            .check_expected(&[int(n, 32)], &[int(expected, 64)])
+            .unwrap();
+    }
+}
+
+#[test]
+fn triangle_number() {
+    let workload = Workload::new("triangle_number", "(int64)", "(int64)")
+        .unwrap()
+        .solidity("Computation")
+        .ink("computation");
+    let mut differential = Differential::new(workload).unwrap();
+
+    for n in [0, 1, 100, 3_000_000] {
+        let expected = n * (n + 1) / 2;
+        differential
+            .check_expected(&[int(n, 64)], &[int(expected, 64)])
+            .unwrap();
+    }
+}
+
+#[test]
+fn remainders() {
+    let workload = Workload::new("remainders", "(uint256,uint256)", "(uint256,uint256)")
+        .unwrap()
+        .solidity("Arithmetics");
+    let mut differential = Differential::new(workload).unwrap();
+
+    for (a, b) in [(0, 0), (1, 2), (u64::MAX, 3)] {
+        let outcome = differential.check(&[uint(a, 256), uint(b, 256)]).unwrap();
+        assert!(matches!(outcome, Outcome::Returned(_)));
+    }
+}
+
+#[test]
+fn fibonacci() {
+    let workload = Workload::new("fib", "(uint32)", "(uint256)")
+        .unwrap()
+        .solidity("FibonacciIterative")
+        .solidity("FibonacciBinet")
+        // fib(370) fits `uint256`, but the closed form overflows computing fib(371) on the way
+        .allow_divergence_on(|args| args == [uint(370, 32)]);
+    let mut differential = Differential::new(workload).unwrap();
+
+    let mut fib = (0u64, 1u64);
+    for n in 0..=64 {
+        differential
+            .check_expected(&[uint(n, 32)], &[uint(fib.0, 256)])
+            .unwrap();
+        fib = (fib.1, fib.0.wrapping_add(fib.1));
+    }
+    differential.check(&[uint(320, 32)]).unwrap();
+    assert!(matches!(
+        differential.check(&[uint(370, 32)]).unwrap(),
+        Outcome::Returned(_)
+    ));
+    // overflows on all backends
+    assert!(matches!(
+        differential.check(&[uint(371, 32)]).unwrap(),
+        Outcome::Failed(_)
+    ));
+}
+
+#[test]
+fn sha256_chain() {
+    let workload = Workload::new("sha256_chain", "(uint32)", "(bytes32)")
+        .unwrap()
+        .solidity("Crypto");
+    let mut differential = Differential::new(workload).unwrap();
+
+    for n in [0, 1, 100] {
+        let expected = (0..n).fold([0u8; 32], |hash, _| sp_core::hashing::sha2_256(&hash));
+        let expected = DynSolValue::FixedBytes(expected.into(), 32);
+        differential
+            .check_expected(&[uint(n, 32)], &[expected])
+            .unwrap();
+    }
+}
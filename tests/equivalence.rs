@@ -0,0 +1,150 @@
+//! Property based checks that every implementation of a workload computes the same results on all
+//! backends for randomly generated inputs, and the results of a Rust reference model where there is
+//! one.
+//!
+//! Any divergence is shrunk to a minimal failing input. The number of cases per workload defaults
+//! to 32, and can be changed with the `PROPTEST_CASES` environment variable.
+
+use std::cell::RefCell;
+
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_primitives::{I256, U256};
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestCaseError, TestRunner},
+};
+use schlau::differential::{Differential, Workload};
+
+fn config() -> Config {
+    let mut config = Config {
+        source_file: Some(file!()),
+        ..Config::default()
+    };
+    if std::env::var("PROPTEST_CASES").is_err() {
+        config.cases = 32;
+    }
+    config
+}
+
+/// Check that all backends agree for all inputs generated by `args`.
+fn check_equivalence<S>(workload: Workload, args: S)
+where
+    S: Strategy<Value = Vec<DynSolValue>>,
+{
+    run(workload, args, |differential, args| {
+        differential.check(args).map(|_| ())
+    });
+}
+
+/// Check that all backends return the outputs of the `reference` model for all inputs generated by
+/// `args`.
+fn check_reference<S, R>(workload: Workload, args: S, reference: R)
+where
+    S: Strategy<Value = Vec<DynSolValue>>,
+    R: Fn(&[DynSolValue]) -> Vec<DynSolValue>,
+{
+    run(workload, args, |differential, args| {
+        differential.check_expected(args, &reference(args))
+    });
+}
+
+fn run<S, F>(workload: Workload, args: S, check: F)
+where
+    S: Strategy<Value = Vec<DynSolValue>>,
+    F: Fn(&mut Differential, &[DynSolValue]) -> anyhow::Result<()>,
+{
+    let differential = RefCell::new(Differential::new(workload).unwrap());
+    let mut runner = TestRunner::new(config());
+    let result = runner.run(&args, |args| {
+        check(&mut differential.borrow_mut(), &args)
+            .map_err(|err| TestCaseError::fail(err.to_string()))
+    });
+    if let Err(err) = result {
+        panic!("{err}");
+    }
+}
+
+/// Generate arbitrary values of the given types, covering their full range.
+fn arb_args(types: &[DynSolType]) -> BoxedStrategy<Vec<DynSolValue>> {
+    types.iter().map(arb_value).collect::<Vec<_>>().boxed()
+}
+
+fn arb_value(ty: &DynSolType) -> BoxedStrategy<DynSolValue> {
+    let raw = any::<[u8; 32]>().prop_map(U256::from_be_bytes);
+    match ty {
+        DynSolType::Bool => any::<bool>().prop_map(DynSolValue::Bool).boxed(),
+        DynSolType::Uint(bits) => {
+            let bits = *bits;
+            raw.prop_map(move |value| DynSolValue::Uint(value >> (256 - bits), bits))
+                .boxed()
+        }
+        DynSolType::Int(bits) => {
+            let bits = *bits;
+            // arithmetic shift right sign extends the truncated value
+            raw.prop_map(move |value| DynSolValue::Int(I256::from_raw(value).asr(256 - bits), bits))
+                .boxed()
+        }
+        DynSolType::Tuple(types) => arb_args(types).prop_map(DynSolValue::Tuple).boxed(),
+        ty => panic!("No strategy for {ty}"),
+    }
+}
+
+fn int(n: i64, bits: usize) -> DynSolValue {
+    DynSolValue::Int(I256::try_from(n).unwrap(), bits)
+}
+
+fn int_arg(args: &[DynSolValue]) -> i64 {
+    match &args[0] {
+        DynSolValue::Int(n, _) => n.as_i64(),
+        arg => panic!("Expected an int, got {arg:?}"),
+    }
+}
+
+#[test]
+fn odd_product() {
+    let workload = Workload::new("odd_product", "(int32)", "(int64)")
+        .unwrap()
+        .solidity("Computation")
+        .ink("computation");
+    // bounded to keep the number of loop iterations reasonable, the product still overflows
+    let args = (-100i32..50_000).prop_map(|n| vec![int(n.into(), 32)]);
+    check_reference(workload, args, |args| {
+        let prod = (1..=int_arg(args)).fold(1i64, |prod, x| prod.wrapping_mul(2 * x - 1));
+        vec![int(prod, 64)]
+    });
+}
+
+#[test]
+fn triangle_number() {
+    let workload = Workload::new("triangle_number", "(int64)", "(int64)")
+        .unwrap()
+        .solidity("Computation")
+        .ink("computation");
+    let args = (-100i64..50_000).prop_map(|n| vec![int(n, 64)]);
+    check_reference(workload, args, |args| {
+        let n = int_arg(args).max(0);
+        vec![int(n * (n + 1) / 2, 64)]
+    });
+}
+
+#[test]
+fn remainders() {
+    let workload = Workload::new("remainders", "(uint256,uint256)", "(uint256,uint256)")
+        .unwrap()
+        .solidity("Arithmetics");
+    let args = arb_args(workload.inputs());
+    check_equivalence(workload, args);
+}
+
+#[test]
+fn fibonacci() {
+    let workload = Workload::new("fib", "(uint32)", "(uint256)")
+        .unwrap()
+        .solidity("FibonacciIterative")
+        .solidity("FibonacciBinet")
+        // fib(370) fits `uint256`, but the closed form overflows computing fib(371) on the way
+        .allow_divergence_on(|args| args == [DynSolValue::Uint(U256::from(370), 32)]);
+    // larger inputs overflow `uint256` and fail on all backends
+    let args = (0u32..1_000).prop_map(|n| vec![DynSolValue::Uint(U256::from(n), 32)]);
+    check_equivalence(workload, args);
+}